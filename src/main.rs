@@ -8,6 +8,7 @@ use std::process;
 mod maze;
 #[path = "minesweeper/minesweeper.rs"]
 mod minesweeper;
+mod records;
 #[path = "snake-game/snake-game.rs"]
 mod snake_game;
 
@@ -17,19 +18,318 @@ struct Game {
     func: fn() -> Result<(), Box<dyn std::error::Error>>, // Function with error handling
 }
 
+/// Runs a game named on the command line, skipping the menu.
+///
+/// `games-in-rust maze [--algorithm NAME] [--size SIZE] [--solver NAME] [--braid
+/// PERCENT] [--sparse PERCENT]` plays a maze generated by the named algorithm at the
+/// given size, picking either from a menu if not given, with hints found by the
+/// named solver and the given share of dead ends braided into loops or rooms filled.
+/// `--fog`, `--fog-radius N` and `--torch SECONDS` play it in the fog of war, and
+/// `--load PATH` plays a maze drawn in a text file, or every maze file in a
+/// directory as a level pack, instead of generating one. `--chase DIFFICULTY` lets
+/// minotaurs hunt the player in real time, and `--minotaurs N` sets how many.
+/// `games-in-rust maze generate` writes a maze as ASCII, SVG or PNG without playing
+/// it, and `games-in-rust maze bench` times generating and rendering large mazes.
+///
+/// `games-in-rust minesweeper [WIDTH HEIGHT MINES] [--hex | --orthogonal | --knight]
+/// [--torus] [--layers N]` plays Minesweeper on a board of the given size and
+/// topology, where a layered board has N layers of HEIGHT rows each, and
+/// `games-in-rust minesweeper --load FILE` plays a saved board. `--lives N` lets
+/// either survive N - 1 mines, and `--auto-flag` and `--auto-open` start it with
+/// the assists on. The `bench`, `flags` and `tutorial` subcommands run the autoplay
+/// benchmark, two-player Minesweeper Flags and the pattern tutorial.
+///
+/// # Returns
+///
+/// `None` if no game was named, otherwise the result of running it.
+fn run_from_args(args: &[String]) -> Option<Result<(), Box<dyn std::error::Error>>> {
+    match args.first().map(String::as_str) {
+        Some("maze") => Some(maze_from_args(&args[1..])),
+        Some("minesweeper") => Some(minesweeper_from_args(&args[1..])),
+        Some(other) => Some(Err(format!("Unknown game: {}", other).into())),
+        None => None,
+    }
+}
+
+/// Parses the options of `games-in-rust maze` and plays the game.
+fn maze_from_args(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    const USAGE: &str = "Usage: games-in-rust maze [--algorithm NAME] \
+                         [--size small | medium | large | huge | fit | WIDTHxHEIGHT] \
+                         [--solver bfs | a-star | dead-end-filling | wall-follower] \
+                         [--braid PERCENT] [--sparse PERCENT] [--fog] [--fog-radius N] [--torch SECONDS] \
+                         [--load FILE | DIRECTORY] [--chase easy | normal | hard] [--minotaurs N]";
+
+    match args.first().map(String::as_str) {
+        Some("bench") => return maze_bench_from_args(&args[1..]),
+        Some("generate") => return maze_generate_from_args(&args[1..]),
+        _ => (),
+    }
+
+    let mut options = maze::Options::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--algorithm" => options.algorithm = Some(args.next().ok_or(USAGE)?.clone()),
+            "--size" => options.size = Some(args.next().ok_or(USAGE)?.clone()),
+            "--solver" => options.solver = Some(args.next().ok_or(USAGE)?.clone()),
+            "--braid" => options.braid = args.next().ok_or(USAGE)?.parse()?,
+            "--sparse" => options.sparse = args.next().ok_or(USAGE)?.parse()?,
+            "--fog" => options.fog = true,
+            "--fog-radius" => options.fog_radius = Some(args.next().ok_or(USAGE)?.parse()?),
+            "--torch" => options.torch = Some(args.next().ok_or(USAGE)?.parse()?),
+            "--load" => options.load = Some(args.next().ok_or(USAGE)?.into()),
+            "--chase" => options.chase = Some(args.next().ok_or(USAGE)?.clone()),
+            "--minotaurs" => options.minotaurs = Some(args.next().ok_or(USAGE)?.parse()?),
+            _ => return Err(USAGE.into()),
+        }
+    }
+    maze::maze_with_options(&options)
+}
+
+/// Parses the options of `games-in-rust maze bench` and runs the benchmark.
+///
+/// The size defaults to 2001x2001, a maze of a million rooms, and every algorithm
+/// is timed unless one is named.
+fn maze_bench_from_args(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    const USAGE: &str = "Usage: games-in-rust maze bench [--algorithm NAME] \
+                         [--size small | medium | large | huge | WIDTHxHEIGHT] [--seed S]";
+
+    let (mut algorithm, mut size, mut seed) = (None, "2001x2001", 0);
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--algorithm" => algorithm = Some(args.next().ok_or(USAGE)?.as_str()),
+            "--size" => size = args.next().ok_or(USAGE)?.as_str(),
+            "--seed" => seed = args.next().ok_or(USAGE)?.parse()?,
+            _ => return Err(USAGE.into()),
+        }
+    }
+    maze::benchmark(algorithm, size, seed)
+}
+
+/// Parses the options of `games-in-rust maze generate` and writes the maze.
+fn maze_generate_from_args(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    const USAGE: &str = "Usage: games-in-rust maze generate [--algorithm NAME] \
+                         [--size small | medium | large | huge | WIDTHxHEIGHT] [--braid PERCENT] [--sparse PERCENT] \
+                         [--seed S] [--format ascii | svg | png] [--output FILE] [--cell PIXELS] [--stroke PIXELS] \
+                         [--solution] [--solver NAME]";
+
+    let mut options = maze::Options::default();
+    let mut export = maze::Export::default();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--algorithm" => options.algorithm = Some(args.next().ok_or(USAGE)?.clone()),
+            "--size" => options.size = Some(args.next().ok_or(USAGE)?.clone()),
+            "--solver" => options.solver = Some(args.next().ok_or(USAGE)?.clone()),
+            "--braid" => options.braid = args.next().ok_or(USAGE)?.parse()?,
+            "--sparse" => options.sparse = args.next().ok_or(USAGE)?.parse()?,
+            "--seed" => export.seed = Some(args.next().ok_or(USAGE)?.parse()?),
+            "--format" => export.format = Some(args.next().ok_or(USAGE)?.clone()),
+            "--output" => export.output = Some(args.next().ok_or(USAGE)?.into()),
+            "--cell" => export.cell = args.next().ok_or(USAGE)?.parse()?,
+            "--stroke" => export.stroke = args.next().ok_or(USAGE)?.parse()?,
+            "--solution" => export.solution = true,
+            _ => return Err(USAGE.into()),
+        }
+    }
+    maze::generate(&options, &export)
+}
+
+/// Parses the board size and topology for `games-in-rust minesweeper` and plays the game.
+fn minesweeper_from_args(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    match args.first().map(String::as_str) {
+        Some("bench") => return minesweeper_bench_from_args(&args[1..]),
+        Some("tutorial") => return minesweeper::minesweeper_tutorial(),
+        Some("flags") => {
+            // Minesweeper Flags is classically played on a 16x16 board with an odd
+            // number of mines, so it cannot end in a draw.
+            let (width, height, mines, topology) = parse_minesweeper_board(&args[1..], (16, 16, 51))?;
+            minesweeper::minesweeper_flags(width, height, mines, topology);
+            return Ok(());
+        }
+        _ => (),
+    }
+
+    // Take out the game options, leaving the board.
+    let mut options = minesweeper::Options::default();
+    let mut board = Vec::new();
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--lives" => {
+                options.lives = rest.next().ok_or("--lives needs a number")?.parse()?;
+                if options.lives == 0 {
+                    return Err("The game needs at least one life.".into());
+                }
+            }
+            "--auto-flag" => options.auto_flag = true,
+            "--auto-open" => options.auto_open = true,
+            _ => board.push(arg.clone()),
+        }
+    }
+
+    if let [flag, path] = board.as_slice() {
+        if flag == "--load" {
+            return minesweeper::minesweeper_from_file(std::path::Path::new(path), options);
+        }
+    }
+
+    let (width, height, mines, topology) = parse_minesweeper_board(&board, (10, 10, 10))?;
+    minesweeper::minesweeper(width, height, mines, topology, options);
+    Ok(())
+}
+
+/// Parses a Minesweeper board size and topology from the command line.
+///
+/// # Arguments
+///
+/// * `args`: The arguments, `[WIDTH HEIGHT MINES]` mixed with topology flags.
+/// * `default`: The width, height and mines used when no size is given.
+///
+/// # Returns
+///
+/// The width, the height of each layer, the mines and the topology of the board.
+fn parse_minesweeper_board(
+    args: &[String],
+    default: (usize, usize, usize),
+) -> Result<(usize, usize, usize, minesweeper::Topology), Box<dyn std::error::Error>> {
+    const USAGE: &str = "Usage: games-in-rust minesweeper [flags] [WIDTH HEIGHT MINES] \
+                         [--hex | --orthogonal | --knight] [--torus] [--layers N] [--lives N] [--auto-flag] [--auto-open] | --load FILE";
+
+    // Split the topology flags from the board size.
+    let mut topology = minesweeper::Topology::default();
+    let mut size = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.strip_prefix("--") {
+            Some("torus") => topology.wrap = true,
+            Some("layers") => topology.layers = args.next().ok_or(USAGE)?.parse()?,
+            Some(name) => {
+                topology.neighbourhood = minesweeper::Neighbourhood::from_name(name).ok_or(USAGE)?;
+            }
+            None => size.push(arg.parse::<usize>()?),
+        }
+    }
+    let (width, height, mines) = match size.as_slice() {
+        [] => default,
+        &[width, height, mines] => (width, height, mines),
+        _ => return Err(USAGE.into()),
+    };
+    check_minesweeper_board(width, height, mines, topology)?;
+    Ok((width, height, mines, topology))
+}
+
+/// Checks a Minesweeper board size from the command line, before any of the board
+/// is allocated.
+///
+/// # Returns
+///
+/// An error if the board has no cells, no safe cell or more than
+/// `minesweeper::MAX_CELLS` cells, or if the topology does not fit it.
+fn check_minesweeper_board(
+    width: usize,
+    height: usize,
+    mines: usize,
+    topology: minesweeper::Topology,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // The product is checked, as a huge width, height or layer count would overflow.
+    let cells = width.checked_mul(height).and_then(|cells| cells.checked_mul(topology.layers));
+    match cells {
+        Some(cells) if cells <= minesweeper::MAX_CELLS => {
+            if cells == 0 || mines >= cells {
+                return Err("The board must have at least one cell and one safe cell.".into());
+            }
+        }
+        _ => return Err(format!("The board can have at most {} cells.", minesweeper::MAX_CELLS).into()),
+    }
+    topology.check(width, height * topology.layers)?;
+    Ok(())
+}
+
+/// Parses the options of `games-in-rust minesweeper bench` and runs the benchmark.
+///
+/// The boards are given as `beginner`, `intermediate`, `expert` or `WIDTHxHEIGHT/MINES`,
+/// and default to the three classic difficulties.
+fn minesweeper_bench_from_args(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    const USAGE: &str = "Usage: games-in-rust minesweeper bench [--games N] [--seed S] \
+                         [--strategy probability | simple] [--hex | --orthogonal | --knight] [--torus] \
+                         [--layers N] [beginner | intermediate | expert | WIDTHxHEIGHT/MINES ...]";
+
+    let mut topology = minesweeper::Topology::default();
+    let mut strategy = minesweeper::Strategy::Probability;
+    let (mut games, mut seed) = (100, 0);
+    let mut sizes = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.strip_prefix("--") {
+            Some("games") => games = args.next().ok_or(USAGE)?.parse()?,
+            Some("seed") => seed = args.next().ok_or(USAGE)?.parse()?,
+            Some("strategy") => {
+                strategy = minesweeper::Strategy::from_name(args.next().ok_or(USAGE)?).ok_or(USAGE)?;
+            }
+            Some("torus") => topology.wrap = true,
+            Some("layers") => topology.layers = args.next().ok_or(USAGE)?.parse()?,
+            Some(name) => {
+                topology.neighbourhood = minesweeper::Neighbourhood::from_name(name).ok_or(USAGE)?;
+            }
+            None => sizes.push(match arg.as_str() {
+                "beginner" => (9, 9, 10),
+                "intermediate" => (16, 16, 40),
+                "expert" => (30, 16, 99),
+                custom => {
+                    let (size, mines) = custom.split_once('/').ok_or(USAGE)?;
+                    let (width, height) = size.split_once('x').ok_or(USAGE)?;
+                    (width.parse()?, height.parse()?, mines.parse()?)
+                }
+            }),
+        }
+    }
+    if sizes.is_empty() {
+        sizes = vec![(9, 9, 10), (16, 16, 40), (30, 16, 99)];
+    }
+    for &(width, height, mines) in &sizes {
+        check_minesweeper_board(width, height, mines, topology)?;
+    }
+    minesweeper::benchmark(&sizes, topology, strategy, games, seed);
+    Ok(())
+}
+
 /// The main entry point of the application.
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Start a game straight away if one was named on the command line.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(result) = run_from_args(&args) {
+        return result;
+    }
+
     // Create a vector of Games, using a struct for better organization.
     // The `Game` struct encapsulates the name of the game and a function to
     // execute the game.
-    let games = vec![
+    let games = [
         Game {
             name: "Maze".to_string(),
             func: || { maze::maze(); Ok(()) }, // Call the maze::maze function
         },
+        Game {
+            name: "Custom Mazes".to_string(),
+            func: maze::custom_mazes, // Call the maze::custom_mazes function
+        },
+        Game {
+            name: "Maze Chase".to_string(),
+            func: || { maze::maze_chase(); Ok(()) }, // Call the maze::maze_chase function
+        },
         Game {
             name: "Minesweeper".to_string(),
-            func: || { minesweeper::minesweeper(10, 10, 10); Ok(()) }, // Call the minesweeper::minesweeper function
+            func: || { minesweeper::minesweeper(10, 10, 10, minesweeper::Topology::default(), minesweeper::Options::default()); Ok(()) }, // Call the minesweeper::minesweeper function
+        },
+        Game {
+            name: "Minesweeper Flags (2 players)".to_string(),
+            func: || { minesweeper::minesweeper_flags(16, 16, 51, minesweeper::Topology::default()); Ok(()) }, // Call the minesweeper::minesweeper_flags function
+        },
+        Game {
+            name: "Minesweeper Tutorial".to_string(),
+            func: minesweeper::minesweeper_tutorial, // Call the minesweeper::minesweeper_tutorial function
         },
         Game {
             name: "Snake".to_string(),
@@ -0,0 +1,255 @@
+use super::fog;
+use super::solver::{AStar, MazeSolver};
+use super::utils::Maze;
+use rand::seq::SliceRandom;
+use rand::RngCore;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// How hard the minotaurs hunt the player.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Difficulty {
+    /// One slow minotaur that has to see the player to hunt them.
+    Easy,
+    /// Two minotaurs at a walking pace that have to see the player to hunt them.
+    Normal,
+    /// Three fast minotaurs that always know where the player is.
+    Hard,
+}
+
+impl Difficulty {
+    /// Returns every difficulty, from the easiest.
+    pub fn all() -> [Difficulty; 3] {
+        [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard]
+    }
+
+    /// Returns the name used for the difficulty on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    /// Returns the difficulty with the given name.
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        Difficulty::all().into_iter().find(|difficulty| difficulty.name() == name)
+    }
+
+    /// Returns the number of minotaurs in the maze.
+    pub fn minotaurs(self) -> u32 {
+        match self {
+            Difficulty::Easy => 1,
+            Difficulty::Normal => 2,
+            Difficulty::Hard => 3,
+        }
+    }
+
+    /// Returns how long a minotaur takes for each step.
+    fn step(self) -> Duration {
+        match self {
+            Difficulty::Easy => Duration::from_millis(600),
+            Difficulty::Normal => Duration::from_millis(400),
+            Difficulty::Hard => Duration::from_millis(250),
+        }
+    }
+
+    /// Returns whether the minotaurs know where the player is through the walls.
+    fn through_walls(self) -> bool {
+        self == Difficulty::Hard
+    }
+}
+
+/// An enemy that hunts the player through the maze.
+pub struct Minotaur {
+    /// Where the minotaur stands.
+    pub position: (i32, i32),
+    /// The cell the minotaur last came from, so it does not turn back while it
+    /// wanders.
+    from: (i32, i32),
+    /// Where the minotaur last saw the player, until it gets there.
+    last_seen: Option<(i32, i32)>,
+}
+
+/// The minotaurs of a chase, which move towards the player on a timer.
+///
+/// A minotaur that can see the player, or that sees through walls, takes the
+/// shortest path towards them. One that has lost sight of the player goes to where
+/// it last saw them, and after that wanders the corridors until it sees them again.
+pub struct Chase {
+    /// The minotaurs in the maze.
+    pub minotaurs: Vec<Minotaur>,
+    /// How long a minotaur takes for each step.
+    step: Duration,
+    /// Whether the minotaurs know where the player is through the walls.
+    through_walls: bool,
+    /// When the minotaurs take their next step.
+    next_step: Instant,
+}
+
+impl Chase {
+    /// Spawns the minotaurs in the far half of the maze from the start, so the
+    /// player has a head start.
+    ///
+    /// # Arguments
+    ///
+    /// * `maze`: The maze.
+    /// * `difficulty`: How fast the minotaurs are and whether they see through walls.
+    /// * `count`: The number of minotaurs. A maze too small for them all gets fewer.
+    /// * `rng`: The random number generator that picks where they spawn and wander.
+    pub fn new(maze: &Maze, difficulty: Difficulty, count: u32, rng: &mut dyn RngCore) -> Chase {
+        // Find how far each cell is from the start.
+        let mut distances = vec![u32::MAX; maze.cell_count()];
+        let mut queue = VecDeque::from([maze.start]);
+        distances[maze.index(maze.start)] = 0;
+        let mut cells = Vec::new();
+        while let Some(cell) = queue.pop_front() {
+            cells.push(cell);
+            for next in maze.open_neighbours(cell) {
+                if distances[maze.index(next)] == u32::MAX {
+                    distances[maze.index(next)] = distances[maze.index(cell)] + 1;
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        // Spawn them at random among the cells at least half as far as the farthest.
+        let farthest = distances[maze.index(*cells.last().unwrap_or(&maze.start))];
+        let mut far: Vec<(i32, i32)> = cells
+            .into_iter()
+            .filter(|&cell| 2 * distances[maze.index(cell)] >= farthest && cell != maze.start && cell != maze.target)
+            .collect();
+        far.shuffle(rng);
+        let minotaurs = far
+            .into_iter()
+            .take(count as usize)
+            .map(|position| Minotaur { position, from: position, last_seen: None })
+            .collect();
+
+        Chase {
+            minotaurs,
+            step: difficulty.step(),
+            through_walls: difficulty.through_walls(),
+            next_step: Instant::now() + difficulty.step(),
+        }
+    }
+
+    /// Moves the minotaurs for every step that is due since the last update.
+    ///
+    /// The player is caught if a minotaur steps onto them, or if they walked onto a
+    /// minotaur since the last update. That is checked before the minotaurs move, so
+    /// a minotaur whose step is due cannot walk away from under the player, and a
+    /// player and a minotaur cannot swap cells: the player moves between the
+    /// minotaurs' steps, so stepping onto the cell of a minotaur that is about to
+    /// step onto theirs is caught here.
+    ///
+    /// # Arguments
+    ///
+    /// * `maze`: The maze.
+    /// * `player`: The position of the player.
+    /// * `rng`: The random number generator that picks where they wander.
+    ///
+    /// # Returns
+    ///
+    /// Whether a minotaur caught the player.
+    pub fn update(&mut self, maze: &Maze, player: (i32, i32), rng: &mut dyn RngCore) -> bool {
+        if self.catches(player) {
+            return true;
+        }
+        while self.next_step <= Instant::now() {
+            self.next_step += self.step;
+            for minotaur in &mut self.minotaurs {
+                let next = minotaur.next_step(maze, player, self.through_walls, rng);
+                minotaur.from = minotaur.position;
+                minotaur.position = next;
+            }
+            if self.catches(player) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Returns whether a minotaur stands on the player's cell.
+    pub fn catches(&self, player: (i32, i32)) -> bool {
+        self.minotaurs.iter().any(|minotaur| minotaur.position == player)
+    }
+}
+
+impl Minotaur {
+    /// Returns the cell the minotaur steps to next.
+    fn next_step(&mut self, maze: &Maze, player: (i32, i32), through_walls: bool, rng: &mut dyn RngCore) -> (i32, i32) {
+        if through_walls || fog::line_of_sight(maze, self.position, player) {
+            self.last_seen = Some(player);
+        }
+        if self.last_seen == Some(self.position) {
+            self.last_seen = None;
+        }
+
+        // Hunt the player down the shortest path to where they were last seen.
+        if let Some(goal) = self.last_seen {
+            if let Some(path) = AStar.solve(maze, self.position, goal) {
+                return path.get(1).copied().unwrap_or(self.position);
+            }
+        }
+
+        // Otherwise wander, only turning back at a dead end.
+        let ways: Vec<(i32, i32)> = maze.open_neighbours(self.position).filter(|&cell| cell != self.from).collect();
+        ways.choose(rng).copied().unwrap_or(self.from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::maze_file;
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    /// Returns a chase with one minotaur at `position` in a straight corridor, whose
+    /// step is already due.
+    fn corridor_chase(position: (i32, i32)) -> (Maze, Chase) {
+        let maze = maze_file::from_text("#########\n#S     E#\n#########\n").unwrap();
+        let chase = Chase {
+            minotaurs: vec![Minotaur { position, from: position, last_seen: None }],
+            step: Duration::from_millis(250),
+            through_walls: false,
+            next_step: Instant::now(),
+        };
+        (maze, chase)
+    }
+
+    #[test]
+    fn walking_onto_a_minotaur_whose_step_is_due_is_a_catch() {
+        let (maze, mut chase) = corridor_chase((4, 1));
+        assert!(chase.update(&maze, (4, 1), &mut StdRng::seed_from_u64(0)));
+    }
+
+    #[test]
+    fn swapping_cells_with_a_minotaur_is_a_catch() {
+        // The player at (3, 1) steps onto the minotaur at (4, 1) just as the minotaur's
+        // step onto (3, 1) becomes due.
+        let (maze, mut chase) = corridor_chase((4, 1));
+        chase.next_step = Instant::now() + Duration::from_secs(60);
+        assert!(!chase.update(&maze, (3, 1), &mut StdRng::seed_from_u64(0)));
+        chase.next_step = Instant::now();
+        assert!(chase.update(&maze, (4, 1), &mut StdRng::seed_from_u64(0)));
+    }
+
+    #[test]
+    fn a_minotaur_in_sight_steps_towards_the_player() {
+        let (maze, mut chase) = corridor_chase((6, 1));
+        assert!(!chase.update(&maze, (1, 1), &mut StdRng::seed_from_u64(0)));
+        assert_eq!(chase.minotaurs[0].position, (5, 1));
+    }
+
+    #[test]
+    fn reaching_the_target_where_a_minotaur_stands_is_a_catch() {
+        // The minotaur guards the end, and its next step is a long way off.
+        let (maze, mut chase) = corridor_chase((7, 1));
+        chase.next_step = Instant::now() + Duration::from_secs(60);
+        assert!(!chase.update(&maze, (6, 1), &mut StdRng::seed_from_u64(0)));
+        assert!(chase.update(&maze, maze.target, &mut StdRng::seed_from_u64(0)));
+    }
+}
@@ -0,0 +1,487 @@
+use super::utils::Maze;
+use std::path::Path;
+
+/// A file format a maze can be written in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Plain text: `#` for walls, spaces for passages, `S` and `E` for the start and
+    /// the end, and `.` for the solution.
+    Ascii,
+    /// A scalable vector drawing, for printing.
+    Svg,
+    /// A bitmap image.
+    Png,
+}
+
+impl Format {
+    /// Parses a format from its name: `ascii`, `svg` or `png`.
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name {
+            "ascii" | "txt" => Some(Format::Ascii),
+            "svg" => Some(Format::Svg),
+            "png" => Some(Format::Png),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format from the extension of a file name: `.txt` or none for
+    /// ASCII, `.svg` or `.png`.
+    ///
+    /// # Returns
+    ///
+    /// The format, or an error for any other extension, so a maze is never written
+    /// as text into a file named like a picture.
+    pub fn from_path(path: &Path) -> Result<Format, String> {
+        let Some(extension) = path.extension() else {
+            return Ok(Format::Ascii);
+        };
+        match extension.to_string_lossy().to_ascii_lowercase().as_str() {
+            "txt" => Ok(Format::Ascii),
+            "svg" => Ok(Format::Svg),
+            "png" => Ok(Format::Png),
+            other => Err(format!(
+                "Unknown file extension: .{}. Use .txt, .svg or .png, or name the format with --format.",
+                other
+            )),
+        }
+    }
+}
+
+/// The sizes used to draw a maze as a picture, in pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Style {
+    /// The width of a room, from wall to wall.
+    pub cell: u32,
+    /// The thickness of the walls.
+    pub stroke: u32,
+}
+
+/// Writes a maze as plain text.
+///
+/// # Arguments
+///
+/// * `maze`: The maze.
+/// * `solution`: The cells to mark as the solution, or an empty slice.
+pub fn ascii(maze: &Maze, solution: &[(i32, i32)]) -> String {
+    let mut rows: Vec<Vec<u8>> = (0..maze.height)
+        .map(|y| (0..maze.width).map(|x| if maze.grid.is_open(x, y) { b' ' } else { b'#' }).collect())
+        .collect();
+    for &(x, y) in solution {
+        rows[y as usize][x as usize] = b'.';
+    }
+    rows[maze.start.1 as usize][maze.start.0 as usize] = b'S';
+    rows[maze.target.1 as usize][maze.target.0 as usize] = b'E';
+
+    let mut text = String::new();
+    for row in rows {
+        text.push_str(&String::from_utf8(row).expect("the maze is written in ASCII"));
+        text.push('\n');
+    }
+    text
+}
+
+/// A straight wall of a maze drawn as a picture, between two corners of the rooms.
+///
+/// Corner `(i, j)` is grid cell `(2i, 2j)`, the top-left corner of room `(i, j)`.
+struct Wall {
+    from: (i32, i32),
+    to: (i32, i32),
+}
+
+/// Returns the walls of a maze as the longest straight runs between room corners.
+///
+/// The outer wall is left open to the left of the start and to the right of the
+/// end, so the way in and out is clear on paper.
+fn walls(maze: &Maze) -> Vec<Wall> {
+    let entrances = entrances(maze);
+    let is_wall = |x: i32, y: i32| !maze.grid.is_open(x, y) && !entrances.contains(&(x, y));
+    let (corners_x, corners_y) = ((maze.width + 1) / 2, (maze.height + 1) / 2);
+    let mut walls = Vec::new();
+
+    // The walls along each row of corners, between the corners of neighbouring rooms.
+    for j in 0..corners_y {
+        let mut run: Option<i32> = None;
+        for i in 0..corners_x {
+            let continues = i + 1 < corners_x && is_wall(2 * i + 1, 2 * j);
+            match (run, continues) {
+                (None, true) => run = Some(i),
+                (Some(start), false) => {
+                    walls.push(Wall { from: (start, j), to: (i, j) });
+                    run = None;
+                }
+                _ => (),
+            }
+        }
+    }
+    // The walls along each column of corners.
+    for i in 0..corners_x {
+        let mut run: Option<i32> = None;
+        for j in 0..corners_y {
+            let continues = j + 1 < corners_y && is_wall(2 * i, 2 * j + 1);
+            match (run, continues) {
+                (None, true) => run = Some(j),
+                (Some(start), false) => {
+                    walls.push(Wall { from: (i, start), to: (i, j) });
+                    run = None;
+                }
+                _ => (),
+            }
+        }
+    }
+    walls
+}
+
+/// Returns the rooms that were filled with rock by a sparse maze.
+fn rock(maze: &Maze) -> Vec<(i32, i32)> {
+    let (columns, rows) = ((maze.width - 1) / 2, (maze.height - 1) / 2);
+    (0..rows)
+        .flat_map(|j| (0..columns).map(move |i| (i, j)))
+        .filter(|&(i, j)| !maze.grid.is_open(2 * i + 1, 2 * j + 1))
+        .collect()
+}
+
+/// Returns the openings in the outer wall of a generated maze: to the left of the
+/// start in the top-left room and to the right of the end in the bottom-right room.
+fn entrances(maze: &Maze) -> [(i32, i32); 2] {
+    [(maze.start.0 - 1, maze.start.1), (maze.target.0 + 1, maze.target.1)]
+}
+
+/// Returns the solution as the cells to draw a line through, going out through the
+/// openings in the outer wall at both ends.
+fn solution_line(maze: &Maze, solution: &[(i32, i32)]) -> Vec<(i32, i32)> {
+    if solution.is_empty() {
+        return Vec::new();
+    }
+    let [entrance, exit] = entrances(maze);
+    let mut line = vec![entrance];
+    line.extend_from_slice(solution);
+    line.push(exit);
+    line
+}
+
+/// Returns the margin around a drawn maze, in pixels.
+fn margin(style: &Style) -> u32 {
+    (style.cell / 2).max(style.stroke)
+}
+
+/// Returns the width and height of a drawn maze, in pixels.
+fn picture_size(maze: &Maze, style: &Style) -> (u32, u32) {
+    let (columns, rows) = (((maze.width - 1) / 2) as u32, ((maze.height - 1) / 2) as u32);
+    (columns * style.cell + 2 * margin(style), rows * style.cell + 2 * margin(style))
+}
+
+/// Returns the thickness of the solution line, in pixels.
+fn solution_stroke(style: &Style) -> u32 {
+    (style.cell / 4).max(1)
+}
+
+/// Writes a maze as an SVG drawing, with the walls as lines.
+///
+/// # Arguments
+///
+/// * `maze`: The maze.
+/// * `solution`: The cells of the solution to draw a line through, or an empty slice.
+/// * `style`: The size of the rooms and the thickness of the walls.
+pub fn svg(maze: &Maze, solution: &[(i32, i32)], style: &Style) -> String {
+    let (width, height) = picture_size(maze, style);
+    let margin = margin(style) as f64;
+    let cell = style.cell as f64;
+    // Corners are spaced a room apart, and grid cells half a room apart.
+    let corner = |c: i32| margin + c as f64 * cell;
+    let grid = |g: i32| margin + g as f64 * cell / 2.0;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+        width, height, width, height
+    );
+    svg.push_str("<rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n");
+    svg.push_str("<g fill=\"black\">\n");
+    for (i, j) in rock(maze) {
+        svg.push_str(&format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>\n",
+            corner(i),
+            corner(j),
+            cell,
+            cell
+        ));
+    }
+    svg.push_str("</g>\n");
+    svg.push_str(&format!(
+        "<g stroke=\"black\" stroke-width=\"{}\" stroke-linecap=\"square\">\n",
+        style.stroke
+    ));
+    for wall in walls(maze) {
+        svg.push_str(&format!(
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"/>\n",
+            corner(wall.from.0),
+            corner(wall.from.1),
+            corner(wall.to.0),
+            corner(wall.to.1)
+        ));
+    }
+    svg.push_str("</g>\n");
+
+    let line = solution_line(maze, solution);
+    if !line.is_empty() {
+        let points: Vec<String> = line.iter().map(|&(x, y)| format!("{},{}", grid(x), grid(y))).collect();
+        svg.push_str(&format!(
+            "<polyline points=\"{}\" fill=\"none\" stroke=\"red\" stroke-width=\"{}\" \
+             stroke-linecap=\"round\" stroke-linejoin=\"round\"/>\n",
+            points.join(" "),
+            solution_stroke(style)
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// The colours of a PNG picture, by their index in its palette.
+const WHITE: u8 = 0;
+const BLACK: u8 = 1;
+const RED: u8 = 2;
+
+/// A bitmap with a palette colour for each pixel.
+struct Bitmap {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Bitmap {
+    /// Fills a rectangle, clipped to the bitmap.
+    fn fill(&mut self, x: i64, y: i64, width: i64, height: i64, colour: u8) {
+        let (left, right) = (x.max(0), (x + width).min(self.width as i64));
+        let (top, bottom) = (y.max(0), (y + height).min(self.height as i64));
+        for row in top..bottom {
+            let start = (row * self.width as i64) as usize;
+            self.pixels[start + left as usize..start + right.max(left) as usize].fill(colour);
+        }
+    }
+
+    /// Draws a horizontal or vertical line `thickness` pixels thick, with square ends.
+    fn line(&mut self, from: (i64, i64), to: (i64, i64), thickness: i64, colour: u8) {
+        let (left, top) = (from.0.min(to.0) - thickness / 2, from.1.min(to.1) - thickness / 2);
+        let (width, height) = ((from.0 - to.0).abs() + thickness, (from.1 - to.1).abs() + thickness);
+        self.fill(left, top, width, height, colour);
+    }
+}
+
+/// Writes a maze as a PNG image, drawn like the SVG drawing.
+///
+/// # Arguments
+///
+/// * `maze`: The maze.
+/// * `solution`: The cells of the solution to draw a line through, or an empty slice.
+/// * `style`: The size of the rooms and the thickness of the walls.
+pub fn png(maze: &Maze, solution: &[(i32, i32)], style: &Style) -> Vec<u8> {
+    let (width, height) = picture_size(maze, style);
+    let mut bitmap = Bitmap { width, height, pixels: vec![WHITE; width as usize * height as usize] };
+    let margin = margin(style) as i64;
+    let cell = style.cell as i64;
+    let corner = |c: i32| margin + c as i64 * cell;
+    let grid = |g: i32| margin + g as i64 * cell / 2;
+
+    for (i, j) in rock(maze) {
+        bitmap.fill(corner(i), corner(j), cell, cell, BLACK);
+    }
+    for wall in walls(maze) {
+        let from = (corner(wall.from.0), corner(wall.from.1));
+        let to = (corner(wall.to.0), corner(wall.to.1));
+        bitmap.line(from, to, style.stroke as i64, BLACK);
+    }
+    // The solution only ever moves between neighbouring cells, so it is made of
+    // horizontal and vertical lines.
+    for step in solution_line(maze, solution).windows(2) {
+        let from = (grid(step[0].0), grid(step[0].1));
+        let to = (grid(step[1].0), grid(step[1].1));
+        bitmap.line(from, to, solution_stroke(style) as i64, RED);
+    }
+    encode_png(&bitmap)
+}
+
+/// Encodes a bitmap as a PNG file with a three colour palette.
+///
+/// The image data is stored in uncompressed deflate blocks, which every PNG reader
+/// understands, so no compression library is needed. The files are larger than
+/// compressed ones, but mazes are small pictures.
+fn encode_png(bitmap: &Bitmap) -> Vec<u8> {
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+
+    // The header: the size, 8 bits per pixel, palette colours, and the standard
+    // compression, filter and interlace methods.
+    let mut header = Vec::new();
+    header.extend_from_slice(&bitmap.width.to_be_bytes());
+    header.extend_from_slice(&bitmap.height.to_be_bytes());
+    header.extend_from_slice(&[8, 3, 0, 0, 0]);
+    chunk(&mut png, b"IHDR", &header);
+    chunk(&mut png, b"PLTE", &[255, 255, 255, 0, 0, 0, 220, 0, 0]);
+
+    // Each row starts with its filter type, 0 for none.
+    let mut raw = Vec::with_capacity(bitmap.pixels.len() + bitmap.height as usize);
+    for row in bitmap.pixels.chunks(bitmap.width as usize) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+    chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+    chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+/// Appends a PNG chunk: its length, type, data and the CRC of the type and data.
+fn chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(kind.iter().chain(data));
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Wraps data in a zlib stream of uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    // The zlib header: deflate with a 32K window, no preset dictionary, and the
+    // check bits that make it a multiple of 31.
+    let mut stream = vec![0x78, 0x01];
+    let mut blocks = data.chunks(u16::MAX as usize).peekable();
+    if blocks.peek().is_none() {
+        // An empty final block.
+        stream.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let length = block.len() as u16;
+        stream.push(u8::from(last));
+        stream.extend_from_slice(&length.to_le_bytes());
+        stream.extend_from_slice(&(!length).to_le_bytes());
+        stream.extend_from_slice(block);
+    }
+    stream.extend_from_slice(&adler32(data).to_be_bytes());
+    stream
+}
+
+/// The CRC-32 checksum PNG chunks end with.
+fn crc32<'a>(bytes: impl Iterator<Item = &'a u8>) -> u32 {
+    let mut crc = u32::MAX;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// The Adler-32 checksum zlib streams end with.
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in bytes {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::generators::{Shape, Wilson};
+    use super::super::maze_file;
+    use super::super::utils::generate_maze;
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    /// A small hand-drawn maze whose way out winds down the left side and along the bottom.
+    const MAZE: &str = "#######\n\
+                        #S    #\n\
+                        # #####\n\
+                        #    E#\n\
+                        #######\n";
+
+    /// Returns the hand-drawn maze.
+    fn maze() -> Maze {
+        maze_file::from_text(MAZE).unwrap()
+    }
+
+    #[test]
+    fn the_format_is_guessed_from_the_extension() {
+        assert_eq!(Format::from_path(Path::new("maze")), Ok(Format::Ascii));
+        assert_eq!(Format::from_path(Path::new("maze.txt")), Ok(Format::Ascii));
+        assert_eq!(Format::from_path(Path::new("maze.SVG")), Ok(Format::Svg));
+        assert_eq!(Format::from_path(Path::new("out/maze.png")), Ok(Format::Png));
+        assert_eq!(
+            Format::from_path(Path::new("maze.jpg")),
+            Err("Unknown file extension: .jpg. Use .txt, .svg or .png, or name the format with --format.".to_string())
+        );
+        assert!(Format::from_path(Path::new("maze.txtx")).is_err());
+    }
+
+    #[test]
+    fn ascii_loads_back_as_the_same_maze() {
+        let maze = maze();
+        assert_eq!(ascii(&maze, &[]), MAZE);
+
+        // The solution is written as dots, which load as passages.
+        let solution = [(1, 1), (1, 2), (1, 3), (2, 3), (3, 3), (4, 3), (5, 3)];
+        let solved = ascii(&maze, &solution);
+        assert_eq!(solved, "#######\n#S    #\n#.#####\n#....E#\n#######\n");
+        assert_eq!(ascii(&maze_file::from_text(&solved).unwrap(), &[]), MAZE);
+    }
+
+    #[test]
+    fn a_seeded_maze_is_written_the_same_every_time() {
+        let write = |seed| {
+            let maze = generate_maze(&Wilson, 21, 11, &Shape::default(), &mut StdRng::seed_from_u64(seed));
+            ascii(&maze, &[])
+        };
+        assert_eq!(write(42), write(42));
+        assert_ne!(write(42), write(43));
+    }
+
+    #[test]
+    fn svg_is_sized_by_the_style() {
+        let maze = maze();
+        let svg = svg(&maze, &[(1, 1), (2, 1)], &Style { cell: 10, stroke: 2 });
+        // Three rooms across and two down, with half a room of margin all round.
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"40\" height=\"30\""), "{}", svg);
+        assert!(svg.contains("<polyline"));
+        assert!(svg.ends_with("</svg>\n"));
+    }
+
+    #[test]
+    fn png_chunks_are_well_formed() {
+        let maze = maze();
+        let png = png(&maze, &[], &Style { cell: 10, stroke: 2 });
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+
+        // Walk the chunks, checking each CRC.
+        let mut kinds = Vec::new();
+        let mut rest = &png[8..];
+        while !rest.is_empty() {
+            let length = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+            let (kind, data) = (&rest[4..8], &rest[8..8 + length]);
+            let crc = u32::from_be_bytes(rest[8 + length..12 + length].try_into().unwrap());
+            assert_eq!(crc32(kind.iter().chain(data)), crc);
+            if kind == b"IHDR" {
+                assert_eq!(&data[..8], [0, 0, 0, 40, 0, 0, 0, 30]);
+            }
+            kinds.push(String::from_utf8(kind.to_vec()).unwrap());
+            rest = &rest[12 + length..];
+        }
+        assert_eq!(kinds, ["IHDR", "PLTE", "IDAT", "IEND"]);
+    }
+
+    #[test]
+    fn the_checksums_match_their_reference_values() {
+        assert_eq!(crc32(b"IEND".iter()), 0xae42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn long_data_is_split_into_stored_blocks() {
+        let data = vec![7; 70_000];
+        let stream = zlib_stored(&data);
+        // Two blocks of five header bytes each, around the zlib header and checksum.
+        assert_eq!(stream.len(), 2 + 5 + 65_535 + 5 + (70_000 - 65_535) + 4);
+        assert_eq!(stream[2], 0);
+        assert_eq!(stream[2 + 5 + 65_535], 1);
+    }
+}
@@ -0,0 +1,230 @@
+use super::utils::Maze;
+use std::time::Instant;
+
+/// The radius of sight a torch starts with, when no radius is given.
+const TORCH_RADIUS: i32 = 8;
+
+/// How much of a cell the player can make out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sight {
+    /// In sight of the player right now.
+    Visible,
+    /// Seen before, and drawn from memory.
+    Remembered,
+    /// Never seen.
+    Unknown,
+}
+
+/// What the player has seen of the maze, for the fog of war.
+///
+/// The player sees every cell within the radius that a straight line from the
+/// player reaches without passing through a wall. Walls are seen, but nothing behind
+/// them. Cells seen once are remembered for the rest of the game.
+pub struct Fog {
+    /// How far the player can see, or `None` to see as far as the lines of sight go.
+    radius: Option<i32>,
+    /// The number of seconds after which a torch loses one cell of its radius, or
+    /// `None` without a torch.
+    torch: Option<u32>,
+    /// When the torch was lit.
+    lit: Instant,
+    /// Whether each cell has ever been seen, by `Maze::index`.
+    seen: Vec<bool>,
+    /// Whether each cell is in sight right now, by `Maze::index`.
+    visible: Vec<bool>,
+    /// The cells in sight right now, so they can be cleared on the next update.
+    in_sight: Vec<usize>,
+    /// The position and radius of the last update, to skip updates that would not
+    /// change anything.
+    last: Option<((i32, i32), i32)>,
+}
+
+impl Fog {
+    /// Creates the fog over a maze that has not been seen at all yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `maze`: The maze.
+    /// * `radius`: How far the player can see, or `None` for as far as the lines of
+    ///   sight go.
+    /// * `torch`: The number of seconds after which the radius shrinks by one cell, or
+    ///   `None` for no torch. A torch without a radius starts at `TORCH_RADIUS`.
+    pub fn new(maze: &Maze, radius: Option<i32>, torch: Option<u32>) -> Fog {
+        let cells = maze.cell_count();
+        let radius = if torch.is_some() { radius.or(Some(TORCH_RADIUS)) } else { radius };
+        Fog {
+            radius,
+            torch,
+            lit: Instant::now(),
+            seen: vec![false; cells],
+            visible: vec![false; cells],
+            in_sight: Vec::new(),
+            last: None,
+        }
+    }
+
+    /// Returns how far the player can see right now, taking the burning down of the
+    /// torch into account. The torch never goes below one cell.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit`: How far to look when there is no radius, usually the size of the
+    ///   screen.
+    pub fn radius(&self, limit: i32) -> i32 {
+        match (self.radius, self.torch) {
+            (Some(radius), Some(seconds)) => {
+                let burnt = (self.lit.elapsed().as_secs() / u64::from(seconds.max(1))) as i32;
+                (radius - burnt).max(1)
+            }
+            (Some(radius), None) => radius,
+            (None, _) => limit,
+        }
+    }
+
+    /// Works out what the player sees from their position, and remembers it.
+    ///
+    /// # Arguments
+    ///
+    /// * `maze`: The maze.
+    /// * `player`: The position of the player.
+    /// * `limit`: How far to look when there is no radius, usually the size of the
+    ///   screen.
+    pub fn update(&mut self, maze: &Maze, player: (i32, i32), limit: i32) {
+        let radius = self.radius(limit);
+        if self.last == Some((player, radius)) {
+            return;
+        }
+        self.last = Some((player, radius));
+
+        for i in self.in_sight.drain(..) {
+            self.visible[i] = false;
+        }
+        for y in (player.1 - radius).max(0)..=(player.1 + radius).min(maze.height - 1) {
+            for x in (player.0 - radius).max(0)..=(player.0 + radius).min(maze.width - 1) {
+                let (dx, dy) = (x - player.0, y - player.1);
+                if dx * dx + dy * dy <= radius * radius && line_of_sight(maze, player, (x, y)) {
+                    let i = maze.index((x, y));
+                    self.visible[i] = true;
+                    self.seen[i] = true;
+                    self.in_sight.push(i);
+                }
+            }
+        }
+    }
+
+    /// Returns how much of a cell the player can make out.
+    pub fn sight(&self, maze: &Maze, cell: (i32, i32)) -> Sight {
+        let i = maze.index(cell);
+        if self.visible[i] {
+            Sight::Visible
+        } else if self.seen[i] {
+            Sight::Remembered
+        } else {
+            Sight::Unknown
+        }
+    }
+}
+
+/// Returns whether a straight line from one cell to another passes only through open
+/// cells. The cells at the ends may be walls.
+///
+/// The line is traced with Bresenham's algorithm, stepping one cell at a time along
+/// the longer side.
+pub fn line_of_sight(maze: &Maze, from: (i32, i32), to: (i32, i32)) -> bool {
+    let (dx, dy) = ((to.0 - from.0).abs(), -(to.1 - from.1).abs());
+    let (step_x, step_y) = ((to.0 - from.0).signum(), (to.1 - from.1).signum());
+    let (mut x, mut y) = from;
+    let mut error = dx + dy;
+
+    loop {
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += step_y;
+        }
+        if (x, y) == to {
+            return true;
+        }
+        if !maze.grid.is_open(x, y) {
+            return false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::maze_file;
+    use super::*;
+    use std::time::Duration;
+
+    /// A corridor with a wall across the middle, and a way round it below.
+    const BLOCKED: &str = "#########\n#S  #  E#\n# ##### #\n#       #\n#########\n";
+    /// A straight corridor from the start to the end.
+    const CORRIDOR: &str = "#########\n#S     E#\n#########\n";
+
+    /// Returns the sight of every cell of the corridor row, from left to right.
+    fn row(fog: &Fog, maze: &Maze) -> Vec<Sight> {
+        (0..maze.width).map(|x| fog.sight(maze, (x, 1))).collect()
+    }
+
+    #[test]
+    fn a_wall_is_seen_but_hides_what_is_behind_it() {
+        let maze = maze_file::from_text(BLOCKED).unwrap();
+        let mut fog = Fog::new(&maze, None, None);
+        fog.update(&maze, maze.start, 20);
+        assert_eq!(fog.sight(&maze, (3, 1)), Sight::Visible);
+        assert_eq!(fog.sight(&maze, (4, 1)), Sight::Visible);
+        assert_eq!(fog.sight(&maze, (5, 1)), Sight::Unknown);
+        assert_eq!(fog.sight(&maze, maze.target), Sight::Unknown);
+        assert!(!line_of_sight(&maze, maze.start, maze.target));
+    }
+
+    #[test]
+    fn the_end_of_a_corridor_is_seen() {
+        let maze = maze_file::from_text(CORRIDOR).unwrap();
+        let mut fog = Fog::new(&maze, None, None);
+        fog.update(&maze, maze.start, 20);
+        assert!(row(&fog, &maze).iter().all(|&sight| sight == Sight::Visible));
+        // The walls next to the player are seen too.
+        assert_eq!(fog.sight(&maze, (2, 0)), Sight::Visible);
+        assert!(line_of_sight(&maze, maze.start, maze.target));
+    }
+
+    #[test]
+    fn cells_out_of_sight_are_remembered() {
+        let maze = maze_file::from_text(CORRIDOR).unwrap();
+        let mut fog = Fog::new(&maze, Some(2), None);
+        fog.update(&maze, (1, 1), 20);
+        assert_eq!(fog.sight(&maze, (3, 1)), Sight::Visible);
+        assert_eq!(fog.sight(&maze, (4, 1)), Sight::Unknown);
+
+        fog.update(&maze, (5, 1), 20);
+        use Sight::*;
+        let sights = [Remembered, Remembered, Remembered, Visible, Visible, Visible, Visible, Visible, Unknown];
+        assert_eq!(row(&fog, &maze), sights);
+    }
+
+    #[test]
+    fn a_torch_burns_down_to_one_cell() {
+        let maze = maze_file::from_text(CORRIDOR).unwrap();
+        assert_eq!(Fog::new(&maze, None, None).radius(20), 20);
+        assert_eq!(Fog::new(&maze, Some(3), None).radius(20), 3);
+        assert_eq!(Fog::new(&maze, None, Some(60)).radius(20), TORCH_RADIUS);
+
+        // Lit 125 seconds ago, a torch losing a cell a minute has lost two.
+        let mut fog = Fog::new(&maze, Some(5), Some(60));
+        fog.lit = Instant::now() - Duration::from_secs(125);
+        assert_eq!(fog.radius(20), 3);
+        fog.lit = Instant::now() - Duration::from_secs(600);
+        assert_eq!(fog.radius(20), 1);
+
+        // A torch of one cell only lights the cells next to the player.
+        fog.update(&maze, (4, 1), 20);
+        assert_eq!(fog.sight(&maze, (5, 1)), Sight::Visible);
+        assert_eq!(fog.sight(&maze, (6, 1)), Sight::Unknown);
+    }
+}
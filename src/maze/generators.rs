@@ -0,0 +1,679 @@
+use super::grid::Grid;
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore};
+use std::collections::HashMap;
+
+/// The rooms of a maze before it is turned into a `Maze`, with the passages carved
+/// between them so far.
+///
+/// Every generator works on the same lattice: room `(x, y)` is grid cell
+/// `(2x + 1, 2y + 1)`, and the grid cell between two neighbouring rooms is opened
+/// when they are connected. The rest of the grid stays wall, so every generator
+/// produces the same `Maze` representation.
+pub struct Cells {
+    /// The number of rooms across.
+    pub columns: i32,
+    /// The number of rooms down.
+    pub rows: i32,
+    /// The open grid cells, as in `Maze::grid`.
+    grid: Grid,
+}
+
+impl Cells {
+    /// Creates a lattice of `columns` by `rows` rooms, all of them still walled in.
+    pub fn new(columns: i32, rows: i32) -> Cells {
+        Cells { columns, rows, grid: Grid::new(2 * columns + 1, 2 * rows + 1) }
+    }
+
+    /// Returns whether the room is inside the lattice.
+    fn contains(&self, (x, y): (i32, i32)) -> bool {
+        (0..self.columns).contains(&x) && (0..self.rows).contains(&y)
+    }
+
+    /// Returns whether the room has been carved, i.e. it is part of the maze.
+    fn is_open(&self, (x, y): (i32, i32)) -> bool {
+        self.grid.is_open(2 * x + 1, 2 * y + 1)
+    }
+
+    /// Carves a room without connecting it to anything.
+    fn open(&mut self, (x, y): (i32, i32)) {
+        self.grid.open(2 * x + 1, 2 * y + 1);
+    }
+
+    /// Carves both rooms and the passage between them. The rooms must be neighbours.
+    fn connect(&mut self, a: (i32, i32), b: (i32, i32)) {
+        self.open(a);
+        self.open(b);
+        self.grid.open(a.0 + b.0 + 1, a.1 + b.1 + 1);
+    }
+
+    /// Walls up a room and the passages out of it, turning it into solid rock.
+    fn close(&mut self, room: (i32, i32)) {
+        for next in self.connections(room) {
+            self.grid.close(room.0 + next.0 + 1, room.1 + next.1 + 1);
+        }
+        self.grid.close(2 * room.0 + 1, 2 * room.1 + 1);
+    }
+
+    /// Returns the neighbouring rooms that a passage leads to from a room.
+    fn connections(&self, room: (i32, i32)) -> Vec<(i32, i32)> {
+        self.neighbours(room)
+            .into_iter()
+            .filter(|&(x, y)| self.grid.is_open(room.0 + x + 1, room.1 + y + 1))
+            .collect()
+    }
+
+    /// Returns whether a room is a dead end: carved, with a single way out.
+    fn is_dead_end(&self, room: (i32, i32)) -> bool {
+        self.is_open(room) && self.connections(room).len() == 1
+    }
+
+    /// Returns the rooms above, below, left and right of a room that are inside the
+    /// lattice.
+    fn neighbours(&self, (x, y): (i32, i32)) -> Vec<(i32, i32)> {
+        [(x, y - 1), (x, y + 1), (x - 1, y), (x + 1, y)]
+            .into_iter()
+            .filter(|&room| self.contains(room))
+            .collect()
+    }
+
+    /// Returns every room, row by row.
+    fn rooms(&self) -> Vec<(i32, i32)> {
+        (0..self.rows).flat_map(|y| (0..self.columns).map(move |x| (x, y))).collect()
+    }
+
+    /// Returns a room chosen uniformly at random.
+    fn random_room(&self, rng: &mut dyn RngCore) -> (i32, i32) {
+        (rng.gen_range(0..self.columns), rng.gen_range(0..self.rows))
+    }
+
+    /// Returns the open grid cells, ready to become a `Maze`.
+    pub fn into_grid(self) -> Grid {
+        self.grid
+    }
+}
+
+/// An algorithm that carves a perfect maze, where every room can be reached from
+/// every other room by exactly one path.
+///
+/// The algorithms differ in the texture of the mazes they make: long winding
+/// corridors, many short dead ends, or a bias towards one corner.
+pub trait MazeGenerator {
+    /// Returns the name used for the algorithm in the menu and on the command line.
+    fn name(&self) -> &'static str;
+
+    /// Carves the passages of the maze into the rooms, connecting all of them.
+    fn carve(&self, cells: &mut Cells, rng: &mut dyn RngCore);
+}
+
+/// Returns every generator, in the order shown in the menu.
+pub fn all() -> Vec<Box<dyn MazeGenerator>> {
+    vec![
+        Box::new(RecursiveBacktracker),
+        Box::new(Prim),
+        Box::new(Kruskal),
+        Box::new(Wilson),
+        Box::new(AldousBroder),
+        Box::new(Eller),
+        Box::new(HuntAndKill),
+        Box::new(BinaryTree),
+        Box::new(Sidewinder),
+        Box::new(GrowingTree { newest: 0.5 }),
+    ]
+}
+
+/// Returns the generator with the given name.
+pub fn from_name(name: &str) -> Option<Box<dyn MazeGenerator>> {
+    all().into_iter().find(|generator| generator.name() == name)
+}
+
+/// Changes a perfect maze after it is carved, to give it loops or rock.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Shape {
+    /// The percentage of dead ends to open into a neighbouring room, each making a
+    /// loop. At 100 there are no dead ends left.
+    pub braid: u32,
+    /// The percentage of rooms to fill with solid rock, by repeatedly filling dead
+    /// ends, so the maze is a thinner network of corridors.
+    pub sparse: u32,
+}
+
+impl Shape {
+    /// Makes the maze sparse, then braids it.
+    ///
+    /// The first and last rooms, where the player and the target start, are never
+    /// filled, and filling only ever removes dead ends, so they stay connected.
+    pub fn apply(&self, cells: &mut Cells, rng: &mut dyn RngCore) {
+        let keep = [(0, 0), (cells.columns - 1, cells.rows - 1)];
+        let rooms = (cells.columns * cells.rows) as u64;
+        let mut to_fill = rooms * u64::from(self.sparse.min(100)) / 100;
+
+        // Fill dead ends picked at random. Filling one can make the room it led to a
+        // dead end, which then becomes a candidate too.
+        let mut dead_ends: Vec<(i32, i32)> = if to_fill > 0 {
+            cells.rooms().into_iter().filter(|&room| cells.is_dead_end(room)).collect()
+        } else {
+            Vec::new()
+        };
+        while to_fill > 0 && !dead_ends.is_empty() {
+            let room = dead_ends.swap_remove(rng.gen_range(0..dead_ends.len()));
+            if keep.contains(&room) || !cells.is_dead_end(room) {
+                continue;
+            }
+            let next = cells.connections(room)[0];
+            cells.close(room);
+            to_fill -= 1;
+            if cells.is_dead_end(next) {
+                dead_ends.push(next);
+            }
+        }
+
+        let mut dead_ends: Vec<(i32, i32)> = cells.rooms().into_iter().filter(|&room| cells.is_dead_end(room)).collect();
+        dead_ends.shuffle(rng);
+        for room in dead_ends {
+            // Opening an earlier dead end into this one may already have fixed it.
+            if !cells.is_dead_end(room) || !rng.gen_bool(f64::from(self.braid.min(100)) / 100.0) {
+                continue;
+            }
+            let walls: Vec<(i32, i32)> = cells
+                .neighbours(room)
+                .into_iter()
+                .filter(|&next| cells.is_open(next) && !cells.connections(room).contains(&next))
+                .collect();
+            // Prefer opening into another dead end, which removes two at once.
+            let dead: Vec<(i32, i32)> = walls.iter().copied().filter(|&next| cells.is_dead_end(next)).collect();
+            if let Some(&next) = dead.choose(rng).or_else(|| walls.choose(rng)) {
+                cells.connect(room, next);
+            }
+        }
+    }
+}
+
+/// Depth-first search: walks to a random unvisited neighbour until it gets stuck,
+/// then backs up to the last room with an unvisited neighbour. Makes long, winding
+/// corridors with few dead ends.
+///
+/// The path back is kept on an explicit stack rather than the call stack, as it can
+/// be as long as the maze has rooms, which would overflow the call stack on a maze
+/// of a few million cells.
+pub struct RecursiveBacktracker;
+
+impl MazeGenerator for RecursiveBacktracker {
+    fn name(&self) -> &'static str {
+        "backtracker"
+    }
+
+    fn carve(&self, cells: &mut Cells, rng: &mut dyn RngCore) {
+        cells.open((0, 0));
+        // The path from the first room to the room being carved from.
+        let mut stack = vec![(0, 0)];
+
+        while let Some(&room) = stack.last() {
+            let unvisited: Vec<(i32, i32)> =
+                cells.neighbours(room).into_iter().filter(|&n| !cells.is_open(n)).collect();
+            match unvisited.choose(rng) {
+                Some(&next) => {
+                    cells.connect(room, next);
+                    stack.push(next);
+                }
+                // Stuck: back up one room.
+                None => {
+                    stack.pop();
+                }
+            }
+        }
+    }
+}
+
+/// Randomised Prim's algorithm: grows the maze from one room by connecting a random
+/// room on its frontier each step. Makes many short dead ends branching off
+/// everywhere.
+pub struct Prim;
+
+impl MazeGenerator for Prim {
+    fn name(&self) -> &'static str {
+        "prim"
+    }
+
+    fn carve(&self, cells: &mut Cells, rng: &mut dyn RngCore) {
+        let start = cells.random_room(rng);
+        cells.open(start);
+        // The rooms next to the maze that are not part of it yet.
+        let mut frontier: Vec<(i32, i32)> = cells.neighbours(start);
+
+        while !frontier.is_empty() {
+            let room = frontier.swap_remove(rng.gen_range(0..frontier.len()));
+            if cells.is_open(room) {
+                continue;
+            }
+            // Connect the room to a random neighbour that is already in the maze.
+            let inside: Vec<(i32, i32)> = cells.neighbours(room).into_iter().filter(|&n| cells.is_open(n)).collect();
+            let &into = inside.choose(rng).expect("a frontier room borders the maze");
+            cells.connect(room, into);
+            frontier.extend(cells.neighbours(room).into_iter().filter(|&n| !cells.is_open(n)));
+        }
+    }
+}
+
+/// Randomised Kruskal's algorithm: knocks down the walls between rooms in a random
+/// order, skipping any wall whose rooms are already connected. Makes a maze with
+/// an even spread of short dead ends.
+pub struct Kruskal;
+
+impl MazeGenerator for Kruskal {
+    fn name(&self) -> &'static str {
+        "kruskal"
+    }
+
+    fn carve(&self, cells: &mut Cells, rng: &mut dyn RngCore) {
+        let rooms = cells.rooms();
+        let columns = cells.columns;
+        let index = |(x, y): (i32, i32)| (y * columns + x) as usize;
+
+        // Every wall between a room and the room to its right or below it.
+        let mut walls: Vec<((i32, i32), (i32, i32))> = Vec::new();
+        for &(x, y) in &rooms {
+            if x + 1 < cells.columns {
+                walls.push(((x, y), (x + 1, y)));
+            }
+            if y + 1 < cells.rows {
+                walls.push(((x, y), (x, y + 1)));
+            }
+        }
+        walls.shuffle(rng);
+
+        // A union-find forest of the rooms that are already connected.
+        let mut parent: Vec<usize> = (0..rooms.len()).collect();
+        fn root(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                // Halve the path on the way up, so later lookups are shorter.
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+
+        for &room in &rooms {
+            cells.open(room);
+        }
+        for (a, b) in walls {
+            let (root_a, root_b) = (root(&mut parent, index(a)), root(&mut parent, index(b)));
+            if root_a != root_b {
+                parent[root_a] = root_b;
+                cells.connect(a, b);
+            }
+        }
+    }
+}
+
+/// Wilson's algorithm: adds each room by a random walk from it that stops on reaching
+/// the maze, erasing any loops the walk made. Every possible maze is equally likely,
+/// so it has no bias at all.
+pub struct Wilson;
+
+impl MazeGenerator for Wilson {
+    fn name(&self) -> &'static str {
+        "wilson"
+    }
+
+    fn carve(&self, cells: &mut Cells, rng: &mut dyn RngCore) {
+        let mut rooms = cells.rooms();
+        rooms.shuffle(rng);
+        cells.open(rooms[0]);
+        let columns = cells.columns;
+        let index = |(x, y): (i32, i32)| (y * columns + x) as usize;
+        // The last way out of each room on the current walk. Only the rooms of the
+        // current walk are read, so it is never cleared.
+        let mut exits: Vec<(i32, i32)> = vec![(0, 0); rooms.len()];
+
+        for &start in &rooms[1..] {
+            if cells.is_open(start) {
+                continue;
+            }
+            // Walk until the maze is reached, remembering only the last way out of
+            // each room, which erases the loops.
+            let mut room = start;
+            while !cells.is_open(room) {
+                let &next = cells.neighbours(room).choose(rng).expect("every room has a neighbour");
+                exits[index(room)] = next;
+                room = next;
+            }
+            // Carve the loop-erased path. It is traced before carving, as carving a
+            // passage opens the room at its far end too.
+            let mut path = vec![start];
+            while !cells.is_open(*path.last().unwrap()) {
+                path.push(exits[index(*path.last().unwrap())]);
+            }
+            for step in path.windows(2) {
+                cells.connect(step[0], step[1]);
+            }
+        }
+    }
+}
+
+/// The Aldous-Broder algorithm: walks at random, connecting each room the first time
+/// it is entered. Like Wilson's algorithm it has no bias, but it can take a long time
+/// to find the last few rooms.
+pub struct AldousBroder;
+
+impl MazeGenerator for AldousBroder {
+    fn name(&self) -> &'static str {
+        "aldous-broder"
+    }
+
+    fn carve(&self, cells: &mut Cells, rng: &mut dyn RngCore) {
+        let mut room = cells.random_room(rng);
+        cells.open(room);
+        let mut remaining = cells.columns * cells.rows - 1;
+
+        while remaining > 0 {
+            let &next = cells.neighbours(room).choose(rng).expect("every room has a neighbour");
+            if !cells.is_open(next) {
+                cells.connect(room, next);
+                remaining -= 1;
+            }
+            room = next;
+        }
+    }
+}
+
+/// Eller's algorithm: builds the maze one row at a time, keeping track only of which
+/// rooms of the current row are already connected. Randomly joins neighbouring rooms
+/// in a row, then carries every group down at least once.
+pub struct Eller;
+
+impl MazeGenerator for Eller {
+    fn name(&self) -> &'static str {
+        "eller"
+    }
+
+    fn carve(&self, cells: &mut Cells, rng: &mut dyn RngCore) {
+        let columns = cells.columns as usize;
+        // The set of each room of the current row, by column.
+        let mut sets: Vec<usize> = (0..columns).collect();
+        let mut next_set = columns;
+
+        for y in 0..cells.rows {
+            let last_row = y + 1 == cells.rows;
+            for x in 0..cells.columns {
+                cells.open((x, y));
+            }
+
+            // Join neighbouring rooms in different sets, at random except on the last
+            // row, where every set must be joined into one.
+            for x in 0..columns - 1 {
+                if sets[x] != sets[x + 1] && (last_row || rng.gen_bool(0.5)) {
+                    cells.connect((x as i32, y), (x as i32 + 1, y));
+                    let (from, into) = (sets[x + 1], sets[x]);
+                    for set in sets.iter_mut().filter(|set| **set == from) {
+                        *set = into;
+                    }
+                }
+            }
+            if last_row {
+                break;
+            }
+
+            // Carry each set down at least once, and the rooms below that are not
+            // carried down start sets of their own.
+            let mut below: Vec<Option<usize>> = vec![None; columns];
+            let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+            for (x, &set) in sets.iter().enumerate() {
+                groups.entry(set).or_default().push(x);
+            }
+            let mut groups: Vec<Vec<usize>> = groups.into_values().collect();
+            // The map's order is not random, so sort before using the generator.
+            groups.sort();
+            for mut members in groups {
+                members.shuffle(rng);
+                let down = rng.gen_range(1..=members.len());
+                for &x in &members[..down] {
+                    cells.connect((x as i32, y), (x as i32, y + 1));
+                    below[x] = Some(sets[x]);
+                }
+            }
+            for (x, set) in below.into_iter().enumerate() {
+                sets[x] = set.unwrap_or_else(|| {
+                    next_set += 1;
+                    next_set
+                });
+            }
+        }
+    }
+}
+
+/// The hunt-and-kill algorithm: walks to random unvisited neighbours until stuck,
+/// then hunts row by row for an unvisited room next to the maze and walks on from
+/// there. Makes long corridors like the backtracker, without keeping a stack.
+pub struct HuntAndKill;
+
+impl MazeGenerator for HuntAndKill {
+    fn name(&self) -> &'static str {
+        "hunt-and-kill"
+    }
+
+    fn carve(&self, cells: &mut Cells, rng: &mut dyn RngCore) {
+        let mut room = Some(cells.random_room(rng));
+        cells.open(room.unwrap());
+        // Every row above this one is fully carved, so hunts start here.
+        let mut hunt_row = 0;
+
+        while let Some(current) = room {
+            // Kill: walk to a random unvisited neighbour.
+            let unvisited: Vec<(i32, i32)> =
+                cells.neighbours(current).into_iter().filter(|&n| !cells.is_open(n)).collect();
+            if let Some(&next) = unvisited.choose(rng) {
+                cells.connect(current, next);
+                room = Some(next);
+                continue;
+            }
+
+            // Hunt: find the first unvisited room next to the maze and join it on.
+            // Rows with nothing left to carve are skipped in every later hunt.
+            while hunt_row < cells.rows && (0..cells.columns).all(|x| cells.is_open((x, hunt_row))) {
+                hunt_row += 1;
+            }
+            room = None;
+            'hunt: for y in hunt_row..cells.rows {
+                for x in 0..cells.columns {
+                    let candidate = (x, y);
+                    if cells.is_open(candidate) {
+                        continue;
+                    }
+                    let visited: Vec<(i32, i32)> =
+                        cells.neighbours(candidate).into_iter().filter(|&n| cells.is_open(n)).collect();
+                    if let Some(&into) = visited.choose(rng) {
+                        cells.connect(candidate, into);
+                        room = Some(candidate);
+                        break 'hunt;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// The binary tree algorithm: connects every room either up or left, at random. It
+/// needs no memory at all, but the top row and left column are always straight
+/// corridors and every path drifts towards the top left.
+pub struct BinaryTree;
+
+impl MazeGenerator for BinaryTree {
+    fn name(&self) -> &'static str {
+        "binary-tree"
+    }
+
+    fn carve(&self, cells: &mut Cells, rng: &mut dyn RngCore) {
+        for (x, y) in cells.rooms() {
+            cells.open((x, y));
+            let choices: Vec<(i32, i32)> = [(x, y - 1), (x - 1, y)].into_iter().filter(|&n| cells.contains(n)).collect();
+            if let Some(&next) = choices.choose(rng) {
+                cells.connect((x, y), next);
+            }
+        }
+    }
+}
+
+/// The sidewinder algorithm: carves each row in runs going right, closing each run
+/// with one passage up from a random room in it. The top row is one long corridor,
+/// and paths upwards are straightforward.
+pub struct Sidewinder;
+
+impl MazeGenerator for Sidewinder {
+    fn name(&self) -> &'static str {
+        "sidewinder"
+    }
+
+    fn carve(&self, cells: &mut Cells, rng: &mut dyn RngCore) {
+        for y in 0..cells.rows {
+            let mut run_start = 0;
+            for x in 0..cells.columns {
+                cells.open((x, y));
+                let at_right = x + 1 == cells.columns;
+                // The top row has nothing above it, so it is one run.
+                let close_run = y > 0 && (at_right || rng.gen_bool(0.5));
+                if close_run {
+                    let up = rng.gen_range(run_start..=x);
+                    cells.connect((up, y), (up, y - 1));
+                    run_start = x + 1;
+                } else if !at_right {
+                    cells.connect((x, y), (x + 1, y));
+                }
+            }
+        }
+    }
+}
+
+/// The growing tree algorithm: keeps a list of rooms to grow from, and each step
+/// grows from either the newest or a random one. Always picking the newest makes it
+/// the backtracker and always picking at random makes it like Prim's algorithm, so
+/// `newest` blends the two textures.
+pub struct GrowingTree {
+    /// The chance of growing from the newest room rather than a random one.
+    pub newest: f64,
+}
+
+impl MazeGenerator for GrowingTree {
+    fn name(&self) -> &'static str {
+        "growing-tree"
+    }
+
+    fn carve(&self, cells: &mut Cells, rng: &mut dyn RngCore) {
+        let start = cells.random_room(rng);
+        cells.open(start);
+        let mut active = vec![start];
+
+        while !active.is_empty() {
+            let newest = rng.gen_bool(self.newest);
+            let i = if newest { active.len() - 1 } else { rng.gen_range(0..active.len()) };
+            let unvisited: Vec<(i32, i32)> =
+                cells.neighbours(active[i]).into_iter().filter(|&n| !cells.is_open(n)).collect();
+            match unvisited.choose(rng) {
+                Some(&next) => {
+                    cells.connect(active[i], next);
+                    active.push(next);
+                }
+                None if newest => {
+                    active.pop();
+                }
+                // Removing from the middle would shift every newer room down a place,
+                // which makes the random picks quadratic on a large maze, so the last
+                // room is swapped into the gap instead.
+                None => {
+                    active.swap_remove(i);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::collections::{HashSet, VecDeque};
+
+    /// Carves a lattice of rooms with a fixed seed.
+    fn carve(generator: &dyn MazeGenerator, columns: i32, rows: i32, seed: u64) -> Cells {
+        let mut cells = Cells::new(columns, rows);
+        generator.carve(&mut cells, &mut StdRng::seed_from_u64(seed));
+        cells
+    }
+
+    /// Returns the open grid cells of a lattice, row by row.
+    fn open_cells(cells: Cells) -> Vec<(i32, i32)> {
+        let (width, height) = (2 * cells.columns + 1, 2 * cells.rows + 1);
+        let grid = cells.into_grid();
+        (0..height).flat_map(|y| (0..width).map(move |x| (x, y))).filter(|&(x, y)| grid.is_open(x, y)).collect()
+    }
+
+    /// Returns how many of the open cells can be reached from the first room.
+    fn reachable(open: &[(i32, i32)]) -> usize {
+        let open: HashSet<(i32, i32)> = open.iter().copied().collect();
+        let mut seen = HashSet::from([(1, 1)]);
+        let mut queue = VecDeque::from([(1, 1)]);
+        while let Some((x, y)) = queue.pop_front() {
+            for next in [(x, y - 1), (x, y + 1), (x - 1, y), (x + 1, y)] {
+                if open.contains(&next) && seen.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+        seen.len()
+    }
+
+    #[test]
+    fn every_generator_carves_a_perfect_maze() {
+        for generator in all() {
+            for (columns, rows) in [(1, 1), (2, 1), (10, 5), (15, 7), (19, 10)] {
+                let open = open_cells(carve(generator.as_ref(), columns, rows, 1));
+                // Every room is carved and reachable, and a tree of `rooms` rooms has
+                // exactly `rooms - 1` passages, so there are no loops.
+                let rooms = (columns * rows) as usize;
+                assert_eq!(reachable(&open), open.len(), "{} {}x{}", generator.name(), columns, rows);
+                assert_eq!(open.len(), 2 * rooms - 1, "{} {}x{}", generator.name(), columns, rows);
+            }
+        }
+    }
+
+    #[test]
+    fn the_same_seed_makes_the_same_maze() {
+        for generator in all() {
+            let first = open_cells(carve(generator.as_ref(), 15, 7, 42));
+            assert_eq!(first, open_cells(carve(generator.as_ref(), 15, 7, 42)), "{}", generator.name());
+            assert_ne!(first, open_cells(carve(generator.as_ref(), 15, 7, 43)), "{}", generator.name());
+        }
+    }
+
+    #[test]
+    fn a_fully_braided_maze_has_no_dead_ends() {
+        for generator in all() {
+            let mut cells = carve(generator.as_ref(), 15, 7, 3);
+            Shape { braid: 100, sparse: 0 }.apply(&mut cells, &mut StdRng::seed_from_u64(3));
+            assert!(cells.rooms().into_iter().all(|room| !cells.is_dead_end(room)), "{}", generator.name());
+            let open = open_cells(cells);
+            assert_eq!(reachable(&open), open.len(), "{}", generator.name());
+        }
+    }
+
+    #[test]
+    fn a_sparse_maze_keeps_the_first_and_last_rooms_connected() {
+        for generator in all() {
+            let mut cells = carve(generator.as_ref(), 15, 7, 5);
+            Shape { braid: 0, sparse: 90 }.apply(&mut cells, &mut StdRng::seed_from_u64(5));
+            assert!(cells.is_open((0, 0)) && cells.is_open((14, 6)), "{}", generator.name());
+            let open = open_cells(cells);
+            assert_eq!(reachable(&open), open.len(), "{}", generator.name());
+            assert!(open.len() < 2 * 15 * 7 - 1, "{}", generator.name());
+        }
+    }
+
+    #[test]
+    fn generators_are_found_by_name() {
+        for generator in all() {
+            assert_eq!(from_name(generator.name()).map(|g| g.name()), Some(generator.name()));
+        }
+        assert!(from_name("labyrinth").is_none());
+    }
+}
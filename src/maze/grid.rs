@@ -0,0 +1,113 @@
+/// The number of cells packed into each word of a `Grid`.
+const BITS: usize = u64::BITS as usize;
+
+/// The open cells of a maze, packed one bit per cell.
+///
+/// A `HashSet` of coordinates costs tens of bytes and a hash per cell, which adds up
+/// to hundreds of megabytes and slow redraws on a maze with millions of cells. Here a
+/// row of a 2001-wide maze fits in 32 words, and looking a cell up is a shift and a
+/// mask.
+#[derive(Clone)]
+pub struct Grid {
+    /// The number of columns.
+    width: i32,
+    /// The number of rows.
+    height: i32,
+    /// The words holding the cells, row by row. Bit `i % 64` of word `i / 64` is set
+    /// when cell `i = y * width + x` is open.
+    bits: Vec<u64>,
+}
+
+impl Grid {
+    /// Creates a grid of `width` by `height` cells, all of them wall.
+    pub fn new(width: i32, height: i32) -> Grid {
+        let cells = (width.max(0) as usize) * (height.max(0) as usize);
+        // One word more than the whole words, for the cells left over.
+        Grid { width, height, bits: vec![0; cells / BITS + 1] }
+    }
+
+    /// Returns the index of a cell in the bits, or `None` if it is outside the grid.
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if (0..self.width).contains(&x) && (0..self.height).contains(&y) {
+            Some(y as usize * self.width as usize + x as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Returns whether the cell is open. Cells outside the grid count as wall.
+    pub fn is_open(&self, x: i32, y: i32) -> bool {
+        self.index(x, y).is_some_and(|i| self.bits[i / BITS] & (1 << (i % BITS)) != 0)
+    }
+
+    /// Opens the cell. The cell must be inside the grid.
+    pub fn open(&mut self, x: i32, y: i32) {
+        let i = self.index(x, y).expect("the cell is inside the grid");
+        self.bits[i / BITS] |= 1 << (i % BITS);
+    }
+
+    /// Walls the cell up again. The cell must be inside the grid.
+    pub fn close(&mut self, x: i32, y: i32) {
+        let i = self.index(x, y).expect("the cell is inside the grid");
+        self.bits[i / BITS] &= !(1 << (i % BITS));
+    }
+
+    /// Returns the number of open cells.
+    pub fn count_open(&self) -> usize {
+        self.bits.iter().map(|word| word.count_ones() as usize).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cells_on_either_side_of_a_word_boundary_are_kept_apart() {
+        // A row of 70 cells puts cells 63 and 64 in different words.
+        let mut grid = Grid::new(70, 2);
+        grid.open(63, 0);
+        assert!(grid.is_open(63, 0));
+        assert!(!grid.is_open(62, 0) && !grid.is_open(64, 0));
+        grid.open(64, 0);
+        assert!(grid.is_open(64, 0));
+        // Cell 127 is the last of the second word, and cell 128 the first of the third.
+        grid.open(127 - 70, 1);
+        grid.open(128 - 70, 1);
+        assert!(grid.is_open(57, 1) && grid.is_open(58, 1) && !grid.is_open(56, 1));
+        assert_eq!(grid.count_open(), 4);
+
+        grid.close(63, 0);
+        assert!(!grid.is_open(63, 0) && grid.is_open(64, 0));
+        assert_eq!(grid.count_open(), 3);
+    }
+
+    #[test]
+    fn the_corners_open_and_everything_outside_is_wall() {
+        // 8x8 is exactly one word, so there is no room to spare past the last cell.
+        for (width, height) in [(8, 8), (5, 3), (1, 1)] {
+            let mut grid = Grid::new(width, height);
+            let corners = [(0, 0), (width - 1, 0), (0, height - 1), (width - 1, height - 1)];
+            for &(x, y) in &corners {
+                grid.open(x, y);
+                assert!(grid.is_open(x, y));
+            }
+            for (x, y) in [(-1, 0), (0, -1), (width, 0), (0, height), (width, height - 1), (i32::MIN, i32::MAX)] {
+                assert!(!grid.is_open(x, y), "({}, {}) on {}x{}", x, y, width, height);
+            }
+            // Only the corners are open, so none of them spilled into the next row.
+            let distinct: std::collections::HashSet<_> = corners.into_iter().collect();
+            assert_eq!(grid.count_open(), distinct.len());
+            if height > 2 {
+                assert!(!grid.is_open(0, 1) && !grid.is_open(width - 1, 1));
+            }
+        }
+    }
+
+    #[test]
+    fn an_empty_grid_has_no_open_cells() {
+        let grid = Grid::new(0, 0);
+        assert_eq!(grid.count_open(), 0);
+        assert!(!grid.is_open(0, 0));
+    }
+}
@@ -1,10 +1,153 @@
 extern crate ncurses;
 extern crate rand;
 use ncurses::*;
+mod chase;
+mod export;
+mod fog;
+mod generators;
+mod grid;
+mod maze_file;
+mod records;
+mod solver;
 mod utils;
+use chase::{Chase, Difficulty};
+use export::{Format, Style};
+use fog::{Fog, Sight};
+use generators::{MazeGenerator, Shape};
+use solver::MazeSolver;
+use rand::SeedableRng;
+use std::time::Instant;
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::PathBuf;
+use utils::{MazeSize, View};
 
-const MAZE_WIDTH: i32 = 31;
-const MAZE_HEIGHT: i32 = 15;
+/// The number of steps of the way out shown by a hint.
+const HINT_STEPS: usize = 10;
+
+/// The choices for a game of Maze. The names are checked when the game starts, and
+/// any algorithm or size not given is picked from a menu.
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    /// The name of the algorithm that generates the maze.
+    pub algorithm: Option<String>,
+    /// The size of the maze, as understood by `MazeSize::from_name`.
+    pub size: Option<String>,
+    /// The name of the algorithm that finds the hints and the way out when the
+    /// player gives up, breadth-first search if not given.
+    pub solver: Option<String>,
+    /// The percentage of dead ends opened up into loops.
+    pub braid: u32,
+    /// The percentage of rooms filled with rock.
+    pub sparse: u32,
+    /// Whether only the cells in the player's line of sight are shown.
+    pub fog: bool,
+    /// How far the player sees in the fog, or `None` for as far as the lines of
+    /// sight go. Turns the fog on.
+    pub fog_radius: Option<u32>,
+    /// The number of seconds after which the player's torch loses one cell of
+    /// radius, or `None` for no torch. Turns the fog on.
+    pub torch: Option<u32>,
+    /// A maze file to play, or a directory of them to play as a level pack, instead
+    /// of generating a maze.
+    pub load: Option<PathBuf>,
+    /// The name of the difficulty of the minotaurs that hunt the player, or `None`
+    /// to play without them.
+    pub chase: Option<String>,
+    /// The number of minotaurs, or `None` for the number the difficulty gives.
+    /// Turns the chase on.
+    pub minotaurs: Option<u32>,
+}
+
+impl Options {
+    /// Returns whether the game is played in the fog of war.
+    fn has_fog(&self) -> bool {
+        self.fog || self.fog_radius.is_some() || self.torch.is_some()
+    }
+
+    /// Returns the shape of the maze, checking the percentages.
+    fn shape(&self) -> Result<Shape, String> {
+        if self.braid > 100 || self.sparse > 100 {
+            return Err("The braid and sparse percentages must be between 0 and 100.".to_string());
+        }
+        Ok(Shape { braid: self.braid, sparse: self.sparse })
+    }
+
+    /// Returns the difficulty of the chase, normal if only the number of minotaurs
+    /// is given, or `None` without a chase.
+    fn difficulty(&self) -> Result<Option<Difficulty>, String> {
+        match (&self.chase, self.minotaurs) {
+            (Some(name), _) => Difficulty::from_name(name).map(Some).ok_or_else(|| {
+                let names: Vec<&str> = Difficulty::all().iter().map(|difficulty| difficulty.name()).collect();
+                format!("Unknown chase difficulty: {}. Choose one of: {}.", name, names.join(", "))
+            }),
+            (None, Some(_)) => Ok(Some(Difficulty::Normal)),
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// Returns the solver named in the options, breadth-first search if none is.
+    fn solver(&self) -> Result<Box<dyn MazeSolver>, String> {
+        let name = self.solver.as_deref().unwrap_or("bfs");
+        solver::from_name(name).ok_or_else(|| {
+            let names: Vec<&str> = solver::all().iter().map(|solver| solver.name()).collect();
+            format!("Unknown maze solver: {}. Choose one of: {}.", name, names.join(", "))
+        })
+    }
+}
+
+/// How `maze generate` writes the maze it generates.
+#[derive(Clone, Debug)]
+pub struct Export {
+    /// The name of the format, or `None` to guess it from the output file, or to
+    /// write ASCII.
+    pub format: Option<String>,
+    /// The file to write, or `None` for the standard output.
+    pub output: Option<PathBuf>,
+    /// The width of a room in the pictures, in pixels.
+    pub cell: u32,
+    /// The thickness of the walls in the pictures, in pixels.
+    pub stroke: u32,
+    /// Whether to draw the solution over the maze.
+    pub solution: bool,
+    /// The seed of the random number generator, or `None` for a random maze.
+    pub seed: Option<u64>,
+}
+
+impl Default for Export {
+    /// A random maze written as ASCII to the standard output, without the solution.
+    fn default() -> Export {
+        Export { format: None, output: None, cell: 16, stroke: 2, solution: false, seed: None }
+    }
+}
+
+/// Returns the generator with the given name.
+///
+/// # Returns
+///
+/// The generator, or an error naming the known algorithms.
+fn generator_named(name: &str) -> Result<Box<dyn MazeGenerator>, String> {
+    generators::from_name(name).ok_or_else(|| {
+        let names: Vec<&str> = generators::all().iter().map(|generator| generator.name()).collect();
+        format!("Unknown maze algorithm: {}. Choose one of: {}.", name, names.join(", "))
+    })
+}
+
+/// Returns the size with the given name, as understood by `MazeSize::from_name`.
+///
+/// # Returns
+///
+/// The size, or an error naming the known sizes, or the limit if the size is too large.
+fn size_named(name: &str) -> Result<MazeSize, String> {
+    let size = MazeSize::from_name(name).ok_or_else(|| {
+        let names: Vec<&str> = utils::PRESETS.iter().map(|(name, _, _)| *name).collect();
+        format!("Unknown maze size: {}. Choose one of: {}, fit or WIDTHxHEIGHT.", name, names.join(", "))
+    })?;
+    if let MazeSize::Fixed(width, height) = size {
+        utils::check_size(width, height)?;
+    }
+    Ok(size)
+}
 
 /*************  ✨ Codeium Command 🌟  *************/
 /// Draws the current state of the maze game to the screen.
@@ -14,81 +157,520 @@ const MAZE_HEIGHT: i32 = 15;
 /// target is represented by the 'X' character. The function makes use of the ncurses
 /// library to handle terminal graphics.
 ///
+/// A maze larger than the terminal scrolls to keep the player in view. In the fog of
+/// war the target is only shown once the player has seen it. The bottom line of the
+/// screen is left for the status bar, which is drawn by the caller.
 ///
 /// * `maze`: A reference to the maze structure that contains the layout of the maze.
 /// * `player_x`: The column index representing the player's current position.
 /// * `player_y`: The row index representing the player's current position.
 /// * `target_x`: The column index representing the target's position.
 /// * `target_y`: The row index representing the target's position.
+/// * `hint`: The next steps of the way out to mark with 'o', or an empty slice.
+/// * `fog`: What the player has seen, in the fog of war mode.
+///
+/// # Returns
+///
+/// The part of the maze that was drawn.
 fn draw_game_state(
     maze: &utils::Maze,
     player_x: i32,
     player_y: i32,
     target_x: i32,
     target_y: i32,
-) -> () {
+    hint: &[(i32, i32)],
+    fog: Option<&Fog>,
+) -> View {
     // Clear the screen to start drawing the updated maze state.
     clear();
 
-    // Draw the maze structure on the screen.
-    // This function is expected to use the ncurses library to render the maze.
-    utils::draw_maze(maze);
+    // Draw the part of the maze around the player on the screen, above the status bar.
+    let view = View::follow(maze, (COLS(), LINES() - 1), (player_x, player_y));
+    utils::draw_maze(maze, &view, fog);
+    draw_path(&view, hint, |_| 'o');
 
     // Place the player character '@' on the screen at the player's current position.
     // The mvaddch function moves the cursor to the specified coordinates and adds
     // the character there.
-    mvaddch(player_y, player_x, '@' as u32);
+    if let Some((x, y)) = view.to_screen((player_x, player_y)) {
+        mvaddch(y, x, '@' as u32);
+    }
 
-    // Place the target character 'X' on the screen at the target's position.
-    mvaddch(target_y, target_x, 'X' as u32);
+    // Place the target character 'X' on the screen at the target's position, if it
+    // is in view and, in the fog, has been seen.
+    let target_sight = fog.map_or(Sight::Visible, |fog| fog.sight(maze, (target_x, target_y)));
+    if let Some((x, y)) = view.to_screen((target_x, target_y)) {
+        match target_sight {
+            Sight::Visible => {
+                mvaddch(y, x, 'X' as u32);
+            }
+            Sight::Remembered => {
+                attron(A_DIM());
+                mvaddch(y, x, 'X' as u32);
+                attroff(A_DIM());
+            }
+            Sight::Unknown => (),
+        }
+    }
 
     // Refresh the screen to reflect all changes made, displaying the updated maze
     // with the player and target positions.
     refresh();
+    view
+}
+
+/// Marks the cells of a path that are in view.
+///
+/// # Arguments
+///
+/// * `view`: The part of the maze on the screen.
+/// * `path`: The cells to mark.
+/// * `mark`: The character to draw on each cell.
+fn draw_path(view: &View, path: &[(i32, i32)], mark: impl Fn((i32, i32)) -> char) {
+    for &cell in path {
+        if let Some((x, y)) = view.to_screen(cell) {
+            mvaddch(y, x, mark(cell) as u32);
+        }
+    }
+}
+
+/// Shows the whole maze with the way out marked with 'o', and the player's trail, if
+/// any, with '.'. Cells on both are marked with '*'.
+///
+/// The view starts on `centre`. Mazes larger than the screen can be looked around
+/// with the arrow keys, and any other key closes the screen.
+///
+/// # Arguments
+///
+/// * `maze`: The maze.
+/// * `centre`: The cell to show in the middle of the screen first.
+/// * `trail`: The cells the player walked through, or an empty slice.
+/// * `route`: The way out.
+/// * `message`: The lines shown below the maze.
+fn review(maze: &utils::Maze, mut centre: (i32, i32), trail: &[(i32, i32)], route: &[(i32, i32)], message: &[String]) {
+    let walked: HashSet<(i32, i32)> = trail.iter().copied().collect();
+    // Wait for the player to read it.
+    timeout(-1);
+    loop {
+        clear();
+        // Leave the bottom lines for the message.
+        let view = View::follow(maze, (COLS(), LINES() - message.len() as i32), centre);
+        utils::draw_maze(maze, &view, None);
+        draw_path(&view, trail, |_| '.');
+        draw_path(&view, route, |cell| if walked.contains(&cell) { '*' } else { 'o' });
+        for (row, line) in (view.rows..).zip(message) {
+            // `mvaddstr` is used as `mvprintw` would treat a `%` as a format directive.
+            mvaddstr(row, 0, line);
+        }
+        refresh();
+
+        // Scroll by half a screen, within the maze.
+        let (dx, dy) = match getch() {
+            KEY_UP => (0, -view.rows / 2),
+            KEY_DOWN => (0, view.rows / 2),
+            KEY_LEFT => (-view.columns / 2, 0),
+            KEY_RIGHT => (view.columns / 2, 0),
+            _ => break,
+        };
+        let (x, y) = (view.x + view.columns / 2 + dx, view.y + view.rows / 2 + dy);
+        centre = (x.clamp(0, maze.width - 1), y.clamp(0, maze.height - 1));
+    }
+    timeout(100);
 }
 
 /// Returns true if the position is within the maze and is not a wall, false otherwise.
 fn is_valid_position(maze: &utils::Maze, x: i32, y: i32) -> bool {
-    x >= 0 && x < MAZE_WIDTH && y >= 0 && y < MAZE_HEIGHT && maze.grid.contains(&(x, y))
+    // The grid counts everything outside the maze as wall.
+    maze.grid.is_open(x, y)
+}
+
+/// Initializes the ncurses library for the maze game.
+fn start_screen() {
+    let window = initscr();
+    noecho(); // Do not echo the keys pressed.
+    curs_set(CURSOR_VISIBILITY::CURSOR_INVISIBLE); // Make the cursor invisible.
+    keypad(window, true); // Enable reading of special keys such as arrow keys.
+    timeout(100); // Set the timeout to 100ms.
+}
+
+/// Shows a menu of options and lets the player pick one with the arrow keys and Enter.
+///
+/// # Arguments
+///
+/// * `title`: The line shown above the options.
+/// * `options`: The names of the options.
+///
+/// # Returns
+///
+/// The index of the chosen option, or `None` if the player pressed q.
+fn pick(title: &str, options: &[String]) -> Option<usize> {
+    let mut selected = 0;
+
+    loop {
+        clear();
+        mvprintw(0, 0, title);
+        for (i, option) in options.iter().enumerate() {
+            let ch = if i == selected { '>' } else { ' ' };
+            mvprintw(i as i32 + 1, 0, &format!("{} {}", ch, option));
+        }
+        refresh();
+
+        match getch() {
+            KEY_UP => selected = (selected + options.len() - 1) % options.len(),
+            KEY_DOWN => selected = (selected + 1) % options.len(),
+            10 => return Some(selected),
+            input if input == 'q' as i32 => return None,
+            _ => (),
+        }
+    }
+}
+
+/// Lets the player pick the algorithm that generates the maze from a menu.
+///
+/// # Returns
+///
+/// The chosen algorithm, or `None` if the player pressed q.
+fn pick_generator() -> Option<Box<dyn MazeGenerator>> {
+    let mut generators = generators::all();
+    let names: Vec<String> = generators.iter().map(|generator| generator.name().to_string()).collect();
+    let selected = pick("Select a maze algorithm:", &names)?;
+    Some(generators.swap_remove(selected))
+}
+
+/// Lets the player pick the size of the maze from a menu of the presets and fitting
+/// the terminal.
+///
+/// # Returns
+///
+/// The chosen size, or `None` if the player pressed q.
+fn pick_size() -> Option<MazeSize> {
+    let mut sizes: Vec<MazeSize> = utils::PRESETS.iter().map(|&(_, width, height)| MazeSize::Fixed(width, height)).collect();
+    let mut names: Vec<String> =
+        utils::PRESETS.iter().map(|(name, width, height)| format!("{} ({}x{})", name, width, height)).collect();
+    sizes.push(MazeSize::FitTerminal);
+    names.push("fit to terminal".to_string());
+    let selected = pick("Select a maze size:", &names)?;
+    Some(sizes[selected])
 }
 
 /// Play a game of Maze.
 /// This function is the entry point for the Maze game. It will initialize the
-/// ncurses library and display the maze and the player's and target's positions.
+/// ncurses library, let the player pick the algorithm that generates the maze and
+/// its size, and then play it.
+pub fn maze() {
+    // The menu offers every choice, so there are no names to get wrong.
+    let _ = maze_with_options(&Options::default());
+}
+
+/// Play a game of Maze with the given options, picking the algorithm and size from a
+/// menu if they are not given.
+///
+/// # Returns
+///
+/// An error naming the known algorithms, sizes or solvers if a name is not one of
+/// them, or if a percentage is over 100.
+pub fn maze_with_options(options: &Options) -> Result<(), Box<dyn std::error::Error>> {
+    // Check the names before the screen is taken over, so the errors can be read.
+    let generator = options.algorithm.as_deref().map(generator_named).transpose()?;
+    let size = options.size.as_deref().map(size_named).transpose()?;
+    let solver = options.solver()?;
+    let shape = options.shape()?;
+    if options.fog_radius == Some(0) || options.torch == Some(0) {
+        return Err("The fog radius and the torch time must be at least 1.".into());
+    }
+    options.difficulty()?;
+    if options.minotaurs == Some(0) {
+        return Err("A chase needs at least one minotaur.".into());
+    }
+    if let Some(path) = &options.load {
+        if generator.is_some() || size.is_some() || shape.braid > 0 || shape.sparse > 0 {
+            return Err("A loaded maze cannot be given an algorithm, a size, braid or sparse.".into());
+        }
+        // Load every level before the screen is taken over, so the errors can be read.
+        let levels = maze_file::load_pack(path)?;
+        start_screen();
+        play_pack(&levels, &*solver, options);
+        endwin(); // Deinitialize the ncurses library.
+        return Ok(());
+    }
+
+    start_screen();
+    play_generated(generator, size, &shape, &*solver, options);
+    endwin(); // Deinitialize the ncurses library.
+    Ok(())
+}
+
+/// Play a game of Maze against the minotaurs, picking their difficulty, the
+/// algorithm and the size from a menu. The dead ends are half braided into loops,
+/// which give the player a way around the minotaurs.
+pub fn maze_chase() {
+    start_screen();
+    let difficulties = Difficulty::all();
+    let names: Vec<String> = difficulties
+        .iter()
+        .map(|difficulty| match difficulty.minotaurs() {
+            1 => format!("{} (1 minotaur)", difficulty.name()),
+            count => format!("{} ({} minotaurs)", difficulty.name(), count),
+        })
+        .collect();
+    if let Some(selected) = pick("Select how hard the minotaurs hunt:", &names) {
+        let options = Options { chase: Some(difficulties[selected].name().to_string()), ..Options::default() };
+        play_generated(None, None, &Shape { braid: 50, sparse: 0 }, &solver::BreadthFirst, &options);
+    }
+    endwin(); // Deinitialize the ncurses library.
+}
+
+/// Generates a maze and plays it, picking the algorithm and size from a menu if
+/// they are not given. The screen must already be started.
+///
+/// # Arguments
+///
+/// * `generator`: The algorithm that generates the maze, or `None` to pick one.
+/// * `size`: The size of the maze, or `None` to pick one.
+/// * `shape`: How much to braid the maze and make it sparse.
+/// * `solver`: The algorithm that finds the way out for hints and giving up.
+/// * `options`: The fog of war and chase settings.
+fn play_generated(
+    generator: Option<Box<dyn MazeGenerator>>,
+    size: Option<MazeSize>,
+    shape: &Shape,
+    solver: &dyn MazeSolver,
+    options: &Options,
+) {
+    if let Some(generator) = generator.or_else(pick_generator) {
+        if let Some(size) = size.or_else(pick_size) {
+            let (width, height) = size.resolve();
+            let maze = utils::generate_maze(&*generator, width, height, shape, &mut rand::thread_rng());
+            let category = category(&maze, generator.name(), shape, options);
+            run(&maze, &category, "", solver, options);
+        }
+    }
+}
+
+/// Play the mazes drawn by hand in `~/.games-in-rust/mazes`, picking one of them or
+/// the whole directory as a level pack from a menu.
+pub fn custom_mazes() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".games-in-rust").join("mazes"));
+    let files = dir.as_deref().map(maze_file::pack_files).unwrap_or(Ok(Vec::new())).unwrap_or_default();
+    let solver = Options::default().solver()?;
+
+    start_screen();
+    if files.is_empty() {
+        let place = dir.map_or("~/.games-in-rust/mazes".to_string(), |dir| dir.display().to_string());
+        show_message(&[
+            format!("There are no mazes in {}.", place),
+            "Draw one in a text file with # for walls, spaces for passages, S for the start".to_string(),
+            "and E for the end, and save it there. Press any key.".to_string(),
+        ]);
+    } else {
+        let mut names = vec!["All of them, as a level pack".to_string()];
+        names.extend(files.iter().map(|file| maze_file::level_name(file)));
+        if let Some(selected) = pick("Select a custom maze:", &names) {
+            let chosen = if selected == 0 { &files[..] } else { &files[selected - 1..selected] };
+            let levels: Result<Vec<_>, String> =
+                chosen.iter().map(|file| Ok((maze_file::level_name(file), maze_file::load(file)?))).collect();
+            match levels {
+                Ok(levels) => play_pack(&levels, &*solver, &Options::default()),
+                Err(e) => show_message(&[e, "Press any key.".to_string()]),
+            }
+        }
+    }
+    endwin(); // Deinitialize the ncurses library.
+    Ok(())
+}
+
+/// Plays loaded mazes one after the other, as the levels of a pack. Reaching the
+/// end of a level, giving up on it or being caught moves on to the next one, and
+/// quitting ends the pack.
+///
+/// # Arguments
+///
+/// * `levels`: The name and maze of each level.
+/// * `solver`: The algorithm that finds the way out for hints and giving up.
+/// * `options`: The fog of war settings.
+fn play_pack(levels: &[(String, utils::Maze)], solver: &dyn MazeSolver, options: &Options) {
+    let mut won = 0;
+    for (i, (name, maze)) in levels.iter().enumerate() {
+        let title = if levels.len() > 1 {
+            format!("Level {}/{}: {}  ", i + 1, levels.len(), name)
+        } else {
+            format!("{}  ", name)
+        };
+        let category = category(maze, &format!("custom {}", name), &Shape::default(), options);
+        match run(maze, &category, &title, solver, options) {
+            Outcome::Won => won += 1,
+            Outcome::GaveUp | Outcome::Caught => (),
+            Outcome::Quit => return,
+        }
+    }
+    if levels.len() > 1 {
+        show_message(&[format!("Level pack finished: {} of {} levels solved. Press any key.", won, levels.len())]);
+    }
+}
+
+/// Shows a message on an empty screen until the player presses a key.
+fn show_message(lines: &[String]) {
+    clear();
+    for (row, line) in lines.iter().enumerate() {
+        mvaddstr(row as i32, 0, line);
+    }
+    refresh();
+    timeout(-1);
+    getch();
+    timeout(100);
+}
+
+/// How a game of Maze ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Outcome {
+    /// The player reached the target.
+    Won,
+    /// The player gave up and was shown the way out.
+    GaveUp,
+    /// A minotaur caught the player.
+    Caught,
+    /// The player quit.
+    Quit,
+}
+
+/// Plays one maze.
 /// The player can move around the maze using the arrow keys. The goal is to
 /// reach the target position.
 /// The game will continue until the player reaches the target position or until
 /// the player presses the 'q' key to quit.
+/// The 'h' key shows the next steps of the way out, and the 'g' key gives up and
+/// shows all of it. Reaching the target shows the player's trail against the
+/// shortest route.
+/// In a chase, minotaurs hunt the player in real time, and the game is lost when one
+/// of them catches the player.
 /// # Arguments
 ///
+/// * `maze`: The maze to play.
+/// * `category`: The kind of maze, under which the game is recorded.
+/// * `title`: The text shown at the start of the status bar, or an empty string.
+/// * `solver`: The algorithm that finds the way out for hints and giving up.
+/// * `options`: The fog of war and chase settings.
 ///
-pub fn maze() {
-    // Check if the maze width and height are valid.
-    assert!(MAZE_WIDTH > 2 && MAZE_HEIGHT > 2, "Maze width and height must be greater than 2.");
-
-    // Initialize ncurses library.
-    let window = initscr();
-    noecho(); // Do not echo the keys pressed.
-    curs_set(CURSOR_VISIBILITY::CURSOR_INVISIBLE); // Make the cursor invisible.
-    keypad(window, true); // Enable reading of special keys such as arrow keys.
-    timeout(100); // Set the timeout to 100ms.
-
-    // Generate a maze.
+/// # Returns
+///
+/// How the game ended.
+fn run(maze: &utils::Maze, category: &str, title: &str, solver: &dyn MazeSolver, options: &Options) -> Outcome {
+    let mut fog = options
+        .has_fog()
+        .then(|| Fog::new(maze, options.fog_radius.map(|radius| radius as i32), options.torch));
+    let mut player_position = maze.start; // The player starts at the start of the maze.
+    let target_position = maze.target; // The target is usually at the bottom right corner of the maze.
+    let mut trail = vec![player_position]; // Every cell the player has stood on, in order.
+    let mut hint: Vec<(i32, i32)> = Vec::new(); // The steps of the way out shown by the last hint.
+    let mut hinted = false; // Whether the player has asked for a hint.
+    // The par is the length of the shortest route to the target.
+    let shortest = solver::BreadthFirst.solve(maze, player_position, target_position).unwrap_or_default();
+    let par = shortest.len().saturating_sub(1) as u32;
+    // The minotaurs hunting the player, if it is a chase.
     let mut rng = rand::thread_rng();
-    let maze = utils::generate_maze(&mut rng);
-    let mut player_position = (1, 1); // The player starts at position (1, 1).
-    let target_position = (MAZE_WIDTH - 2, MAZE_HEIGHT - 2); // The target is at the bottom right corner of the maze.
+    let mut chase = options.difficulty().ok().flatten().map(|difficulty| {
+        let count = options.minotaurs.unwrap_or(difficulty.minotaurs());
+        Chase::new(maze, difficulty, count, &mut rng)
+    });
+    let started = Instant::now(); // The clock starts as soon as the maze is shown.
 
     loop {
-        // Draw the maze and the player's and target's positions.
-        draw_game_state(&maze, player_position.0, player_position.1, target_position.0, target_position.1);
+        // In the fog, look around from where the player stands, as far as the screen
+        // goes at most.
+        if let Some(fog) = &mut fog {
+            fog.update(maze, player_position, COLS().max(LINES()));
+        }
+
+        // Let the minotaurs take the steps that are due, and end the game if one of
+        // them has caught the player, or the player has walked into one.
+        if let Some(chase) = &mut chase {
+            if chase.update(maze, player_position, &mut rng) {
+                let message = [
+                    format!("A minotaur caught you after {:.1}s!", started.elapsed().as_secs_f64()),
+                    "Your trail is marked with a dot. Press any key.".to_string(),
+                ];
+                review(maze, player_position, &trail, &[], &message);
+                return Outcome::Caught;
+            }
+        }
+
+        // If the player has reached the target position, end the game, record it and
+        // compare the player's trail with the shortest route. This comes after the
+        // chase, so walking onto the target where a minotaur stands is still a catch.
+        if player_position == target_position {
+            let record = records::Record {
+                category: category.to_string(),
+                seconds: started.elapsed().as_secs_f64(),
+                moves: trail.len() as u32 - 1,
+                par,
+                hinted,
+            };
+            let message = [
+                format!(
+                    "You Win! Time: {:.1}s  Moves: {}  Par: {}  Rating: {}",
+                    record.seconds,
+                    record.moves,
+                    record.par,
+                    stars(record.stars())
+                ),
+                records_line(&record),
+                "Your trail . shortest o both *. Arrows look around, other keys quit.".to_string(),
+            ];
+            review(maze, player_position, &trail, &shortest, &message);
+            return Outcome::Won;
+        }
+
+        // Draw the maze and the player's and target's positions, and the status bar
+        // below them.
+        let view = draw_game_state(
+            maze,
+            player_position.0,
+            player_position.1,
+            target_position.0,
+            target_position.1,
+            &hint,
+            fog.as_ref(),
+        );
+        // Draw the minotaurs the player can see, which in the fog are the ones in
+        // sight.
+        for minotaur in chase.iter().flat_map(|chase| &chase.minotaurs) {
+            let sight = fog.as_ref().map_or(Sight::Visible, |fog| fog.sight(maze, minotaur.position));
+            if let (Some((x, y)), Sight::Visible) = (view.to_screen(minotaur.position), sight) {
+                mvaddch(y, x, 'M' as u32);
+            }
+        }
+        let status = format!(
+            "{}Time: {}s  Moves: {}  Par: {}  (h hint, g give up, q quit)",
+            title,
+            started.elapsed().as_secs(),
+            trail.len() - 1,
+            par
+        );
+        mvaddstr(view.rows, 0, &status);
+        refresh();
 
         // Get the player's input.
         let input = getch();
 
         // If the player pressed the 'q' key, quit the game.
         if input == 'q' as i32 {
-            break;
+            return Outcome::Quit;
+        }
+
+        // If the player pressed the 'h' key, show the next steps of the way out.
+        if input == 'h' as i32 {
+            let path = way_out(maze, solver, player_position, target_position);
+            hint = path.into_iter().skip(1).take(HINT_STEPS).collect();
+            hinted = true;
+            continue;
+        }
+
+        // If the player pressed the 'g' key, show the whole way out and end the game.
+        if input == 'g' as i32 {
+            let route = way_out(maze, solver, player_position, target_position);
+            let message = ["You gave up. The way out is marked with o. Press any key.".to_string()];
+            review(maze, player_position, &[], &route, &message);
+            return Outcome::GaveUp;
         }
 
         // Determine the new position of the player based on the input.
@@ -101,20 +683,186 @@ pub fn maze() {
         };
 
         // If the new position is within the maze and is not a wall, move the player there.
-        if is_valid_position(&maze, next_x, next_y) {
+        if (next_x, next_y) != player_position && is_valid_position(maze, next_x, next_y) {
             player_position = (next_x, next_y);
+            trail.push(player_position);
+            // Keep the rest of the hint while the player follows it.
+            if hint.first() == Some(&player_position) {
+                hint.remove(0);
+            } else {
+                hint.clear();
+            }
         }
+    }
+}
 
-        // If the player has reached the target position, end the game.
-        if player_position == target_position {
-            clear(); // Clear the screen.
-            mvprintw(MAZE_HEIGHT / 2, MAZE_WIDTH / 2 - 5, "You Win!"); // Print a message to the middle of the screen indicating that the player has won.
-            refresh(); // Refresh the screen to reflect the changes made.
-            napms(2000); // Wait for 2 seconds before ending the game.
-            break;
+/// Finds the way from one cell to another with the chosen solver, falling back to
+/// breadth-first search when the solver finds none. The wall follower can walk in
+/// circles around a loop of a braided maze and give up, but every maze has a way
+/// out.
+fn way_out(maze: &utils::Maze, solver: &dyn MazeSolver, from: (i32, i32), to: (i32, i32)) -> Vec<(i32, i32)> {
+    solver
+        .solve(maze, from, to)
+        .or_else(|| solver::BreadthFirst.solve(maze, from, to))
+        .unwrap_or_default()
+}
+
+/// Returns the records category of a maze: its size and kind, the algorithm that
+/// generated it or the custom maze it was loaded from, and how it was shaped and
+/// played when that differs from a plain maze.
+fn category(maze: &utils::Maze, kind: &str, shape: &Shape, options: &Options) -> String {
+    let mut category = format!("{}x{} {}", maze.width, maze.height, kind);
+    if shape.braid > 0 {
+        category.push_str(&format!(" braid {}%", shape.braid));
+    }
+    if shape.sparse > 0 {
+        category.push_str(&format!(" sparse {}%", shape.sparse));
+    }
+    if options.has_fog() {
+        category.push_str(" fog");
+        if let Some(radius) = options.fog_radius {
+            category.push_str(&format!(" radius {}", radius));
+        }
+        if let Some(seconds) = options.torch {
+            category.push_str(&format!(" torch {}s", seconds));
         }
+    }
+    if let Ok(Some(difficulty)) = options.difficulty() {
+        category.push_str(&format!(" chase {}", difficulty.name()));
+        if let Some(count) = options.minotaurs {
+            category.push_str(&format!(" minotaurs {}", count));
+        }
+    }
+    category
+}
+
+/// Returns a star rating out of three as text, e.g. `**-` for two stars.
+fn stars(stars: u32) -> String {
+    (0..3).map(|star| if star < stars { '*' } else { '-' }).collect()
+}
 
+/// Saves a game to the records and returns the line telling the player how it
+/// compares with the best games of its category.
+fn records_line(record: &records::Record) -> String {
+    if records::save(record).is_err() {
+        return "Could not save the records file.".to_string();
     }
+    if record.hinted {
+        return "Hints were used, so this game is not counted in the records.".to_string();
+    }
+    match records::best(&records::load(), &record.category) {
+        (Some(fastest), Some(fewest)) => {
+            format!("{} best: {:.1}s  fewest moves: {}", record.category, fastest.seconds, fewest.moves)
+        }
+        _ => String::new(),
+    }
+}
 
-    endwin(); // Deinitialize the ncurses library.
+/// Times generating and rendering one maze with each of the given algorithms, and
+/// prints a table of the timings.
+///
+/// Rendering builds the text of every row of the maze, as drawing a screen the size
+/// of the whole maze would, without needing a terminal.
+///
+/// # Arguments
+///
+/// * `algorithm`: The name of the algorithm to time, or `None` for all of them.
+/// * `size`: The size of the maze, as understood by `MazeSize::from_name`, except
+///   `fit`.
+/// * `seed`: The seed of the random number generator, so runs can be compared.
+///
+/// # Returns
+///
+/// An error if the algorithm or size is not known.
+pub fn benchmark(algorithm: Option<&str>, size: &str, seed: u64) -> Result<(), Box<dyn std::error::Error>> {
+    let generators = match algorithm {
+        Some(name) => vec![generator_named(name)?],
+        None => generators::all(),
+    };
+    let (width, height) = match MazeSize::from_name(size) {
+        Some(MazeSize::Fixed(width, height)) => {
+            utils::check_size(width, height)?;
+            utils::normalise_size(width, height)
+        }
+        _ => return Err(format!("The benchmark needs a preset or WIDTHxHEIGHT size, not {}.", size).into()),
+    };
+
+    println!("Size: {}x{}  Seed: {}", width, height, seed);
+    println!("{:<16} {:>12} {:>12} {:>12}", "Algorithm", "Generate ms", "Render ms", "Open cells");
+    for generator in generators {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        let start = Instant::now();
+        let maze = utils::generate_maze(&*generator, width, height, &Shape::default(), &mut rng);
+        let generated = start.elapsed();
+
+        let start = Instant::now();
+        for y in 0..maze.height {
+            // Keep the compiler from skipping rows that are never shown.
+            std::hint::black_box(utils::row_text(&maze, y, 0..maze.width));
+        }
+        let rendered = start.elapsed();
+
+        println!(
+            "{:<16} {:>12.1} {:>12.1} {:>12}",
+            generator.name(),
+            generated.as_secs_f64() * 1000.0,
+            rendered.as_secs_f64() * 1000.0,
+            maze.grid.count_open()
+        );
+    }
+    Ok(())
+}
+
+/// Generates a maze without playing it and writes it to a file or the standard
+/// output, as ASCII, SVG or PNG.
+///
+/// # Arguments
+///
+/// * `options`: The algorithm, size and shape of the maze, and the solver that finds
+///   the solution. The algorithm defaults to the backtracker and the size to `medium`.
+/// * `export`: How to write the maze.
+///
+/// # Returns
+///
+/// An error if a name or the size is not known, or the maze could not be written.
+pub fn generate(options: &Options, export: &Export) -> Result<(), Box<dyn std::error::Error>> {
+    let generator = generator_named(options.algorithm.as_deref().unwrap_or("backtracker"))?;
+    let (width, height) = match size_named(options.size.as_deref().unwrap_or("medium"))? {
+        MazeSize::Fixed(width, height) => (width, height),
+        MazeSize::FitTerminal => return Err("There is no terminal to fit when generating a maze.".into()),
+    };
+    let shape = options.shape()?;
+    let solver = options.solver()?;
+    let format = match (&export.format, &export.output) {
+        (Some(name), _) => {
+            Format::from_name(name).ok_or_else(|| format!("Unknown format: {}. Choose ascii, svg or png.", name))?
+        }
+        (None, Some(path)) => Format::from_path(path)?,
+        (None, None) => Format::Ascii,
+    };
+    if export.cell == 0 {
+        return Err("The cell size must be at least 1 pixel.".into());
+    }
+
+    // The same seed always makes the same maze.
+    let seed = export.seed.unwrap_or_else(rand::random);
+    let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+    let maze = utils::generate_maze(&*generator, width, height, &shape, &mut rng);
+    let solution = if export.solution {
+        way_out(&maze, &*solver, maze.start, maze.target)
+    } else {
+        Vec::new()
+    };
+
+    let style = Style { cell: export.cell, stroke: export.stroke };
+    let bytes = match format {
+        Format::Ascii => export::ascii(&maze, &solution).into_bytes(),
+        Format::Svg => export::svg(&maze, &solution, &style).into_bytes(),
+        Format::Png => export::png(&maze, &solution, &style),
+    };
+    match &export.output {
+        Some(path) => std::fs::write(path, bytes)?,
+        None => std::io::stdout().write_all(&bytes)?,
+    }
+    Ok(())
 }
@@ -0,0 +1,204 @@
+use super::grid::Grid;
+use super::utils::{Maze, MAX_CELLS};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Parses a maze drawn by hand in a text file.
+///
+/// Each line of the file is a row of the maze, with one character per cell:
+///
+/// * `#` - a wall.
+/// * ` ` - a passage. `.` is a passage too, so the mazes written by
+///   `games-in-rust maze generate --solution` load as well.
+/// * `S` - the start, where the player begins.
+/// * `E` - the end, the target the player has to reach.
+///
+/// The maze is as wide as its longest line. Shorter lines are padded with walls, so
+/// a ragged line can never leave a gap in the outer wall, and everything outside the
+/// maze counts as wall too. Blank lines at the end of the file are ignored.
+///
+/// # Returns
+///
+/// The maze, or a message describing the first problem found. There must be exactly
+/// one start and one end, and every passage must be reachable from the start, so a
+/// hand-drawn maze cannot hide a walled-in end or unreachable rooms.
+pub fn from_text(text: &str) -> Result<Maze, String> {
+    let lines: Vec<&str> = text.trim_end_matches(['\n', '\r']).lines().collect();
+    let width = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+    if width == 0 {
+        return Err("The maze file is empty.".to_string());
+    }
+    if width * lines.len() > MAX_CELLS {
+        return Err(format!("The maze can have at most {} cells.", MAX_CELLS));
+    }
+    let (width, height) = (width as i32, lines.len() as i32);
+
+    // Read the cells, all of them wall until a line says otherwise.
+    let mut grid = Grid::new(width, height);
+    let (mut start, mut target) = (None, None);
+    for (y, line) in lines.iter().enumerate() {
+        let y = y as i32;
+        for (x, ch) in line.chars().chain(std::iter::repeat('#')).take(width as usize).enumerate() {
+            let x = x as i32;
+            let mark = match ch {
+                '#' => continue,
+                ' ' | '.' => None,
+                'S' => Some(&mut start),
+                'E' => Some(&mut target),
+                _ => return Err(format!("Line {}: unknown cell `{}` in column {}.", y + 1, ch, x + 1)),
+            };
+            if let Some(mark) = mark {
+                if mark.is_some() {
+                    return Err(format!("Line {}: a second `{}` in column {}.", y + 1, ch, x + 1));
+                }
+                *mark = Some((x, y));
+            }
+            grid.open(x, y);
+        }
+    }
+    let start = start.ok_or("The maze has no start `S`.")?;
+    let target = target.ok_or("The maze has no end `E`.")?;
+    let maze = Maze { width, height, grid, start, target };
+
+    // Walk from the start to every passage it leads to.
+    let mut reached = vec![false; maze.cell_count()];
+    let mut queue = VecDeque::from([start]);
+    reached[maze.index(start)] = true;
+    while let Some(cell) = queue.pop_front() {
+        for next in maze.open_neighbours(cell) {
+            if !reached[maze.index(next)] {
+                reached[maze.index(next)] = true;
+                queue.push_back(next);
+            }
+        }
+    }
+    if !reached[maze.index(target)] {
+        return Err("The end `E` cannot be reached from the start `S`.".to_string());
+    }
+    for y in 0..height {
+        for x in 0..width {
+            if maze.grid.is_open(x, y) && !reached[maze.index((x, y))] {
+                return Err(format!(
+                    "Line {}: the passage in column {} cannot be reached from the start.",
+                    y + 1,
+                    x + 1
+                ));
+            }
+        }
+    }
+
+    Ok(maze)
+}
+
+/// Loads a maze from a maze file.
+pub fn load(path: &Path) -> Result<Maze, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    from_text(&text).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Returns the maze files of a level pack directory, in the order of their names.
+/// Hidden files are skipped.
+pub fn pack_files(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("Could not read {}: {}", dir.display(), e))?;
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|file| file.is_file() && !file.file_name().unwrap_or_default().to_string_lossy().starts_with('.'))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Returns the name of a level, the file name without its extension.
+pub fn level_name(path: &Path) -> String {
+    path.file_stem().unwrap_or_default().to_string_lossy().into_owned()
+}
+
+/// Loads a level pack: every maze file in a directory, or a single maze file for a
+/// pack of one maze.
+///
+/// Every file is loaded before the first maze is played, so a broken level is
+/// reported up front rather than halfway through the pack.
+///
+/// # Returns
+///
+/// The name of each level and its maze, in the order they are played.
+pub fn load_pack(path: &Path) -> Result<Vec<(String, Maze)>, String> {
+    let files = if path.is_dir() { pack_files(path)? } else { vec![path.to_path_buf()] };
+    if files.is_empty() {
+        return Err(format!("{} holds no maze files.", path.display()));
+    }
+    files.iter().map(|file| Ok((level_name(file), load(file)?))).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_hand_drawn_maze_loads() {
+        let maze = from_text("#####\n#S  #\n### #\n#E  #\n#####\n").unwrap();
+        assert_eq!((maze.width, maze.height), (5, 5));
+        assert_eq!((maze.start, maze.target), ((1, 1), (1, 3)));
+        assert!(maze.grid.is_open(3, 2) && !maze.grid.is_open(2, 2));
+    }
+
+    #[test]
+    fn short_lines_are_padded_with_walls() {
+        // The third line stops short, and the file ends with blank lines.
+        let maze = from_text("#####\n#S E#\n#\n#####\n\n\n").unwrap();
+        assert_eq!((maze.width, maze.height), (5, 4));
+        assert!((1..5).all(|x| !maze.grid.is_open(x, 2)));
+
+        // A row cut short of the outer wall is closed off rather than left open.
+        let maze = from_text("######\n#S  E#\n# ##\n######\n").unwrap();
+        assert!(maze.grid.is_open(1, 2));
+        assert!(!maze.grid.is_open(4, 2) && !maze.grid.is_open(5, 2));
+    }
+
+    #[test]
+    fn dots_and_carriage_returns_are_accepted() {
+        let maze = from_text("#####\r\n#S.E#\r\n#####\r\n").unwrap();
+        assert_eq!((maze.width, maze.start, maze.target), (5, (1, 1), (3, 1)));
+    }
+
+    #[test]
+    fn broken_mazes_are_rejected_with_the_first_problem() {
+        let errors = [
+            ("", "The maze file is empty."),
+            ("\n\n", "The maze file is empty."),
+            ("#####\n#S?E#\n#####\n", "Line 2: unknown cell `?` in column 3."),
+            ("#####\n#SSE#\n#####\n", "Line 2: a second `S` in column 3."),
+            ("#####\n#S E#\n#E  #\n#####\n", "Line 3: a second `E` in column 2."),
+            ("#####\n#  E#\n#####\n", "The maze has no start `S`."),
+            ("#####\n#S  #\n#####\n", "The maze has no end `E`."),
+            ("#####\n#S#E#\n#####\n", "The end `E` cannot be reached from the start `S`."),
+            ("######\n#S E##\n#### #\n######\n", "Line 3: the passage in column 5 cannot be reached from the start."),
+        ];
+        for (text, error) in errors {
+            assert_eq!(from_text(text).err().as_deref(), Some(error), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn a_level_pack_is_loaded_in_name_order() {
+        let dir = std::env::temp_dir().join(format!("games-in-rust-pack-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        assert_eq!(load_pack(&dir).err(), Some(format!("{} holds no maze files.", dir.display())));
+        fs::write(dir.join("2-second.txt"), "#####\n#S E#\n#####\n").unwrap();
+        fs::write(dir.join("1-first.txt"), "####\n#SE#\n####\n").unwrap();
+        fs::write(dir.join(".hidden.txt"), "not a maze").unwrap();
+
+        let names: Vec<String> = load_pack(&dir).unwrap().into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names, ["1-first", "2-second"]);
+
+        // One broken level fails the whole pack, naming the file.
+        fs::write(dir.join("3-broken.txt"), "#####\n#S  #\n#####\n").unwrap();
+        let error = load_pack(&dir).err().unwrap();
+        assert!(error.ends_with("3-broken.txt: The maze has no end `E`."), "{}", error);
+
+        fs::remove_dir_all(&dir).unwrap();
+        let error = load_pack(&dir).err().unwrap();
+        assert!(error.starts_with("Could not read"), "{}", error);
+    }
+}
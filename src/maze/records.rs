@@ -0,0 +1,136 @@
+use crate::records::{self, TsvRecord};
+use std::io;
+
+/// The name of the records file in `~/.games-in-rust`.
+const FILE_NAME: &str = "maze-records.tsv";
+
+/// A single maze reached, as stored in the records file.
+#[derive(Clone, Debug)]
+pub struct Record {
+    /// The kind of maze, e.g. `31x15 backtracker` or `61x31 prim braid 50%`, so that
+    /// records are only ever compared against mazes made the same way.
+    pub category: String,
+    /// The elapsed time in seconds.
+    pub seconds: f64,
+    /// The number of moves the player made.
+    pub moves: u32,
+    /// The number of moves of the shortest path from the start to the target.
+    pub par: u32,
+    /// Whether the player asked for a hint, which keeps the game out of the
+    /// comparisons.
+    pub hinted: bool,
+}
+
+impl Record {
+    /// Returns the star rating, from 1 to 3: three stars for reaching the target in
+    /// par, two for at most half as many moves again, and one otherwise. A game with
+    /// hints gets one star.
+    pub fn stars(&self) -> u32 {
+        if self.hinted {
+            1
+        } else if self.moves <= self.par {
+            3
+        } else if 2 * self.moves <= 3 * self.par {
+            2
+        } else {
+            1
+        }
+    }
+}
+
+impl TsvRecord for Record {
+    fn to_line(&self) -> String {
+        format!(
+            "{}\t{:.3}\t{}\t{}\t{}",
+            self.category,
+            self.seconds,
+            self.moves,
+            self.par,
+            if self.hinted { "hinted" } else { "unhinted" },
+        )
+    }
+
+    fn from_line(line: &str) -> Option<Record> {
+        let mut fields = line.split('\t');
+        Some(Record {
+            category: fields.next()?.to_string(),
+            seconds: fields.next()?.parse().ok()?,
+            moves: fields.next()?.parse().ok()?,
+            par: fields.next()?.parse().ok()?,
+            hinted: fields.next()? == "hinted",
+        })
+    }
+}
+
+/// Appends a record to the records file, creating the file if needed.
+pub fn save(record: &Record) -> io::Result<()> {
+    records::save(FILE_NAME, record)
+}
+
+/// Loads every record stored in the records file.
+///
+/// A missing file simply means no mazes have been recorded yet.
+pub fn load() -> Vec<Record> {
+    records::load(FILE_NAME)
+}
+
+/// Returns the best game for a category, by time and by moves. Games with hints are
+/// left out.
+pub fn best(records: &[Record], category: &str) -> (Option<Record>, Option<Record>) {
+    let games = records.iter().filter(|r| !r.hinted && r.category == category);
+    let fastest = games.clone().min_by(|a, b| a.seconds.total_cmp(&b.seconds)).cloned();
+    let fewest = games.min_by_key(|r| r.moves).cloned();
+    (fastest, fewest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns an unhinted game of the medium backtracker maze with the given moves
+    /// and par.
+    fn game(seconds: f64, moves: u32, par: u32) -> Record {
+        Record { category: "31x15 backtracker".to_string(), seconds, moves, par, hinted: false }
+    }
+
+    #[test]
+    fn stars_are_awarded_against_par() {
+        assert_eq!(game(10.0, 40, 40).stars(), 3);
+        assert_eq!(game(10.0, 41, 40).stars(), 2);
+        // Half as many moves again as par is the last move worth two stars.
+        assert_eq!(game(10.0, 60, 40).stars(), 2);
+        assert_eq!(game(10.0, 61, 40).stars(), 1);
+        let mut hinted = game(10.0, 40, 40);
+        hinted.hinted = true;
+        assert_eq!(hinted.stars(), 1);
+    }
+
+    #[test]
+    fn records_survive_a_round_trip_through_a_line() {
+        let mut record = game(12.3456, 52, 48);
+        record.category = "61x31 prim braid 50%".to_string();
+        record.hinted = true;
+        let line = record.to_line();
+        assert_eq!(line, "61x31 prim braid 50%\t12.346\t52\t48\thinted");
+        let read = Record::from_line(&line).unwrap();
+        assert_eq!(read.category, record.category);
+        assert_eq!((read.seconds, read.moves, read.par, read.hinted), (12.346, 52, 48, true));
+
+        assert!(Record::from_line("31x15 backtracker\tslow\t52\t48\thinted").is_none());
+        assert!(Record::from_line("31x15 backtracker\t12.3").is_none());
+    }
+
+    #[test]
+    fn best_skips_hinted_games_and_other_mazes() {
+        let mut hinted = game(5.0, 30, 40);
+        hinted.hinted = true;
+        let mut other = game(4.0, 20, 40);
+        other.category = "21x11 prim".to_string();
+        let records = [hinted, other, game(20.0, 42, 40), game(15.0, 50, 40)];
+
+        let (fastest, fewest) = best(&records, "31x15 backtracker");
+        assert_eq!(fastest.map(|r| r.seconds), Some(15.0));
+        assert_eq!(fewest.map(|r| r.moves), Some(42));
+        assert!(matches!(best(&records, "121x61 kruskal"), (None, None)));
+    }
+}
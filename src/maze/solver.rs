@@ -0,0 +1,341 @@
+use super::utils::Maze;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+
+/// Marks a cell with no parent in the search trees below.
+const NONE: u32 = u32::MAX;
+
+/// An algorithm that finds a way through a maze.
+///
+/// The algorithms differ in what they need to know: breadth-first search and A* see
+/// the whole maze, dead-end filling only looks at cells and their neighbours, and the
+/// wall follower only at the walls next to it, like a player inside the maze.
+pub trait MazeSolver {
+    /// Returns the name used for the algorithm on the command line.
+    fn name(&self) -> &'static str;
+
+    /// Finds a path from `start` to `goal` through open cells.
+    ///
+    /// # Returns
+    ///
+    /// The cells of the path, from `start` to `goal` both included, or `None` if the
+    /// goal cannot be reached.
+    fn solve(&self, maze: &Maze, start: (i32, i32), goal: (i32, i32)) -> Option<Vec<(i32, i32)>>;
+}
+
+/// Returns every solver, in the order listed in the usage.
+pub fn all() -> Vec<Box<dyn MazeSolver>> {
+    vec![Box::new(BreadthFirst), Box::new(AStar), Box::new(DeadEndFilling), Box::new(WallFollower)]
+}
+
+/// Returns the solver with the given name.
+pub fn from_name(name: &str) -> Option<Box<dyn MazeSolver>> {
+    all().into_iter().find(|solver| solver.name() == name)
+}
+
+/// Walks the parents of a search tree back from the goal to the start.
+///
+/// # Arguments
+///
+/// * `maze`: The maze that was searched.
+/// * `parents`: The cell each cell was reached from, by index, or `NONE`.
+/// * `goal`: The cell to walk back from.
+fn trace(maze: &Maze, parents: &[u32], goal: (i32, i32)) -> Vec<(i32, i32)> {
+    let mut path = vec![goal];
+    let mut i = parents[maze.index(goal)];
+    while i != NONE {
+        path.push(maze.position(i as usize));
+        i = parents[i as usize];
+    }
+    path.reverse();
+    path
+}
+
+/// Breadth-first search over the open cells that `allowed` lets through, which
+/// finds a shortest path.
+fn breadth_first(
+    maze: &Maze,
+    start: (i32, i32),
+    goal: (i32, i32),
+    allowed: impl Fn(usize) -> bool,
+) -> Option<Vec<(i32, i32)>> {
+    if !maze.grid.is_open(start.0, start.1) || !maze.grid.is_open(goal.0, goal.1) {
+        return None;
+    }
+    let mut parents = vec![NONE; maze.cell_count()];
+    let mut seen = vec![false; parents.len()];
+    let mut queue = VecDeque::from([start]);
+    seen[maze.index(start)] = true;
+
+    while let Some(cell) = queue.pop_front() {
+        if cell == goal {
+            return Some(trace(maze, &parents, goal));
+        }
+        for next in maze.open_neighbours(cell) {
+            let i = maze.index(next);
+            if !seen[i] && allowed(i) {
+                seen[i] = true;
+                parents[i] = maze.index(cell) as u32;
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+/// Breadth-first search: explores the maze outwards from the start, one step at a
+/// time, so the first path to reach the goal is a shortest one.
+pub struct BreadthFirst;
+
+impl MazeSolver for BreadthFirst {
+    fn name(&self) -> &'static str {
+        "bfs"
+    }
+
+    fn solve(&self, maze: &Maze, start: (i32, i32), goal: (i32, i32)) -> Option<Vec<(i32, i32)>> {
+        breadth_first(maze, start, goal, |_| true)
+    }
+}
+
+/// A* search: like breadth-first search, but explores the cells closest to the goal
+/// as the crow flies first, so it usually looks at fewer cells to find a shortest
+/// path.
+pub struct AStar;
+
+impl MazeSolver for AStar {
+    fn name(&self) -> &'static str {
+        "a-star"
+    }
+
+    fn solve(&self, maze: &Maze, start: (i32, i32), goal: (i32, i32)) -> Option<Vec<(i32, i32)>> {
+        if !maze.grid.is_open(start.0, start.1) || !maze.grid.is_open(goal.0, goal.1) {
+            return None;
+        }
+        // The Manhattan distance never overestimates in a maze without diagonal moves,
+        // so the path found is a shortest one.
+        let estimate = |(x, y): (i32, i32)| ((x - goal.0).abs() + (y - goal.1).abs()) as u32;
+        let mut parents = vec![NONE; maze.cell_count()];
+        // The length of the shortest path found so far to each cell.
+        let mut steps = vec![u32::MAX; parents.len()];
+        // The cells to explore, by the estimated length of a path through them.
+        let mut open = BinaryHeap::new();
+        steps[maze.index(start)] = 0;
+        open.push(Reverse((estimate(start), 0, start)));
+
+        while let Some(Reverse((_, to_cell, cell))) = open.pop() {
+            if cell == goal {
+                return Some(trace(maze, &parents, goal));
+            }
+            // Skip cells that were reached by a shorter path since they were queued.
+            if to_cell > steps[maze.index(cell)] {
+                continue;
+            }
+            for next in maze.open_neighbours(cell) {
+                let i = maze.index(next);
+                if to_cell + 1 < steps[i] {
+                    steps[i] = to_cell + 1;
+                    parents[i] = maze.index(cell) as u32;
+                    open.push(Reverse((to_cell + 1 + estimate(next), to_cell + 1, next)));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Dead-end filling: fills in every dead end, and every corridor that becomes a dead
+/// end as a result, until only the cells on the way from the start to the goal are
+/// left. In a maze with loops the loops are left too, so the rest is searched
+/// breadth-first.
+pub struct DeadEndFilling;
+
+impl MazeSolver for DeadEndFilling {
+    fn name(&self) -> &'static str {
+        "dead-end-filling"
+    }
+
+    fn solve(&self, maze: &Maze, start: (i32, i32), goal: (i32, i32)) -> Option<Vec<(i32, i32)>> {
+        let cells = maze.cell_count();
+        let mut filled = vec![false; cells];
+        // The number of open, unfilled neighbours of each cell.
+        let mut exits = vec![0u8; cells];
+        let mut dead_ends = Vec::new();
+        let keep = [maze.index(start), maze.index(goal)];
+        for y in 0..maze.height {
+            for x in 0..maze.width {
+                if maze.grid.is_open(x, y) {
+                    let i = maze.index((x, y));
+                    exits[i] = maze.open_neighbours((x, y)).count() as u8;
+                    if exits[i] <= 1 && !keep.contains(&i) {
+                        dead_ends.push((x, y));
+                    }
+                }
+            }
+        }
+
+        // Fill each dead end, then check whether its neighbour became one.
+        while let Some(cell) = dead_ends.pop() {
+            filled[maze.index(cell)] = true;
+            for next in maze.open_neighbours(cell) {
+                let i = maze.index(next);
+                if !filled[i] {
+                    exits[i] -= 1;
+                    if exits[i] == 1 && !keep.contains(&i) {
+                        dead_ends.push(next);
+                    }
+                }
+            }
+        }
+        breadth_first(maze, start, goal, |i| !filled[i])
+    }
+}
+
+/// The wall follower: keeps its right hand on the wall and walks until it reaches the
+/// goal, as a player without a map would. The steps it takes back out of dead ends
+/// are erased from the path it returns.
+///
+/// It only finds goals on the same wall as the start, which in a maze without loops
+/// is every goal.
+pub struct WallFollower;
+
+impl MazeSolver for WallFollower {
+    fn name(&self) -> &'static str {
+        "wall-follower"
+    }
+
+    fn solve(&self, maze: &Maze, start: (i32, i32), goal: (i32, i32)) -> Option<Vec<(i32, i32)>> {
+        if !maze.grid.is_open(start.0, start.1) || !maze.grid.is_open(goal.0, goal.1) {
+            return None;
+        }
+        // Up, right, down and left, so turning right is the next direction.
+        const DIRECTIONS: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+        // The place of each cell in the path, so walking back onto the path erases
+        // the detour since.
+        let mut on_path = vec![NONE; maze.cell_count()];
+        let mut path = vec![start];
+        on_path[maze.index(start)] = 0;
+        let (mut cell, mut facing) = (start, 1);
+
+        // Following a wall visits each cell at most once from each side, so a longer
+        // walk means the goal is not on this wall.
+        for _ in 0..4 * on_path.len() {
+            if cell == goal {
+                return Some(path);
+            }
+            // Try right, straight on, left and back, in that order.
+            let turn = [1, 0, 3, 2]
+                .into_iter()
+                .find(|turn| {
+                    let (dx, dy) = DIRECTIONS[(facing + turn) % 4];
+                    maze.grid.is_open(cell.0 + dx, cell.1 + dy)
+                })?;
+            facing = (facing + turn) % 4;
+            let (dx, dy) = DIRECTIONS[facing];
+            cell = (cell.0 + dx, cell.1 + dy);
+
+            let i = maze.index(cell);
+            if on_path[i] == NONE {
+                on_path[i] = path.len() as u32;
+                path.push(cell);
+            } else {
+                for erased in path.drain(on_path[i] as usize + 1..) {
+                    on_path[maze.index(erased)] = NONE;
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::generators::{self, Shape};
+    use super::super::{maze_file, utils};
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    /// A maze with loops: two shortest routes of 7 cells round the upper block of wall,
+    /// and a longer one round the lower block.
+    const TWO_ROUTES: &str = "#######\n#S    #\n# ### #\n#    E#\n# ### #\n#     #\n#######\n";
+    /// A maze whose end sits inside a free-standing ring of wall, so a hand on the
+    /// outer wall never gets there.
+    const ISLAND: &str = "#########\n#S      #\n# ##### #\n# #   # #\n\
+                          # # E # #\n# #   # #\n# ## ## #\n#       #\n#########\n";
+
+    /// Generates a seeded maze of 41x21 cells, braided by `braid` percent.
+    fn generated(braid: u32, seed: u64) -> Maze {
+        let generator = generators::from_name("backtracker").unwrap();
+        let shape = Shape { braid, sparse: 0 };
+        utils::generate_maze(&*generator, 41, 21, &shape, &mut StdRng::seed_from_u64(seed))
+    }
+
+    /// Checks that a path runs from the start to the target in single steps through
+    /// open cells.
+    fn assert_walkable(maze: &Maze, path: &[(i32, i32)]) {
+        assert_eq!(path.first(), Some(&maze.start));
+        assert_eq!(path.last(), Some(&maze.target));
+        assert!(path.iter().all(|&(x, y)| maze.grid.is_open(x, y)));
+        for step in path.windows(2) {
+            let ((x0, y0), (x1, y1)) = (step[0], step[1]);
+            assert_eq!((x0 - x1).abs() + (y0 - y1).abs(), 1, "{:?} is not a step", step);
+        }
+    }
+
+    #[test]
+    fn bfs_and_a_star_find_routes_of_the_same_shortest_length() {
+        let maze = maze_file::from_text(TWO_ROUTES).unwrap();
+        for solver in [&BreadthFirst as &dyn MazeSolver, &AStar] {
+            let path = solver.solve(&maze, maze.start, maze.target).unwrap();
+            assert_walkable(&maze, &path);
+            assert_eq!(path.len(), 7, "{}", solver.name());
+        }
+
+        for seed in 0..5 {
+            let maze = generated(50, seed);
+            let bfs = BreadthFirst.solve(&maze, maze.start, maze.target).unwrap();
+            let a_star = AStar.solve(&maze, maze.start, maze.target).unwrap();
+            assert_walkable(&maze, &bfs);
+            assert_walkable(&maze, &a_star);
+            assert_eq!(bfs.len(), a_star.len());
+        }
+    }
+
+    #[test]
+    fn dead_end_filling_finds_the_route_through_a_perfect_maze() {
+        for seed in 0..5 {
+            let maze = generated(0, seed);
+            let path = DeadEndFilling.solve(&maze, maze.start, maze.target).unwrap();
+            assert_walkable(&maze, &path);
+            // A perfect maze has exactly one route, so it is the shortest one too.
+            assert_eq!(Some(path), BreadthFirst.solve(&maze, maze.start, maze.target));
+        }
+    }
+
+    #[test]
+    fn the_wall_follower_finds_the_target_or_gives_up_in_a_maze_with_loops() {
+        let maze = maze_file::from_text(TWO_ROUTES).unwrap();
+        assert_walkable(&maze, &WallFollower.solve(&maze, maze.start, maze.target).unwrap());
+
+        // The right hand goes round and round the outer wall, and the walk stops.
+        let maze = maze_file::from_text(ISLAND).unwrap();
+        assert!(BreadthFirst.solve(&maze, maze.start, maze.target).is_some());
+        assert_eq!(WallFollower.solve(&maze, maze.start, maze.target), None);
+
+        for seed in 0..5 {
+            let maze = generated(100, seed);
+            if let Some(path) = WallFollower.solve(&maze, maze.start, maze.target) {
+                assert_walkable(&maze, &path);
+            }
+        }
+    }
+
+    #[test]
+    fn every_solver_gives_up_on_walls() {
+        let maze = maze_file::from_text(TWO_ROUTES).unwrap();
+        for solver in all() {
+            assert_eq!(solver.solve(&maze, maze.start, (0, 0)), None, "{}", solver.name());
+            assert_eq!(from_name(solver.name()).map(|found| found.name()), Some(solver.name()));
+        }
+    }
+}
@@ -1,141 +1,306 @@
 extern crate ncurses;
 extern crate rand;
+use super::fog::{Fog, Sight};
+use super::generators::{Cells, MazeGenerator, Shape};
+use super::grid::Grid;
 use ncurses::*;
-use rand::seq::SliceRandom;
-use std::collections::HashSet;
+use std::ops::Range;
 
-const MAZE_WIDTH: i32 = 31;
-const MAZE_HEIGHT: i32 = 15;
+/// The smallest maze: a single room with a wall all around it.
+const MIN_SIZE: i32 = 3;
+
+/// The largest maze, in cells, walls included, that can be generated or loaded. It
+/// keeps every cell index well inside an `i32`, and fits the 2001x2001 benchmark maze
+/// four times over.
+pub const MAX_CELLS: usize = 16_000_000;
+
+/// The named maze sizes, as `(name, width, height)`. `medium` is the classic size.
+pub const PRESETS: [(&str, i32, i32); 4] =
+    [("small", 21, 11), ("medium", 31, 15), ("large", 61, 31), ("huge", 121, 61)];
 
 pub struct Maze {
-    pub grid: HashSet<(i32, i32)>,
+    /// The number of columns of the maze, walls included.
+    pub width: i32,
+    /// The number of rows of the maze, walls included.
+    pub height: i32,
+    /// The open cells of the maze. Everything else is wall.
+    pub grid: Grid,
+    /// Where the player starts.
+    pub start: (i32, i32),
+    /// Where the player has to get to.
+    pub target: (i32, i32),
 }
 
-/// Generates a maze using the recursive backtracking algorithm.
-///
-/// The maze is represented as a `HashSet` of `(i32, i32)` coordinates representing the
-/// cells in the maze. The maze is generated by starting in the top-left corner and then
-/// recursively carving passages in a random order.
+impl Maze {
+    /// Returns the index of a cell inside the maze, row by row, for tables with an
+    /// entry per cell.
+    pub fn index(&self, (x, y): (i32, i32)) -> usize {
+        y as usize * self.width as usize + x as usize
+    }
+
+    /// Returns the number of cells of the maze, walls included, the length of the
+    /// tables indexed by `index`.
+    pub fn cell_count(&self) -> usize {
+        self.width as usize * self.height as usize
+    }
+
+    /// Returns the cell with the given index, the inverse of `index`.
+    pub fn position(&self, i: usize) -> (i32, i32) {
+        (i as i32 % self.width, i as i32 / self.width)
+    }
+
+    /// Returns the open cells above, below, left and right of a cell.
+    pub fn open_neighbours(&self, (x, y): (i32, i32)) -> impl Iterator<Item = (i32, i32)> + '_ {
+        [(x, y - 1), (x, y + 1), (x - 1, y), (x + 1, y)]
+            .into_iter()
+            .filter(|&(x, y)| self.grid.is_open(x, y))
+    }
+}
+
+/// The part of the maze that is shown on the screen.
 ///
-/// The recursive backtracking algorithm works by recursively carving passages in the
-/// maze until all cells have been visited. The algorithm starts by choosing a random
-/// direction to move in, and then checks if the adjacent cell is within the bounds of
-/// the maze and if it is not already a part of the maze. If the adjacent cell is valid,
-/// then the algorithm carves a passage in the adjacent cell and then recursively calls
-/// itself in the adjacent cell. If the adjacent cell is not valid, then the algorithm
-/// moves on to the next direction.
-pub fn generate_maze(rng: &mut impl rand::Rng) -> Maze {
-    let mut grid = HashSet::new();
-
-    /// Recursively carves a passage in the maze.
+/// Mazes larger than the terminal scroll to keep the player in the middle of the
+/// screen, and only the cells inside the view are ever drawn.
+pub struct View {
+    /// The column of the maze shown at the left edge of the screen.
+    pub x: i32,
+    /// The row of the maze shown at the top edge of the screen.
+    pub y: i32,
+    /// The number of maze columns shown.
+    pub columns: i32,
+    /// The number of maze rows shown.
+    pub rows: i32,
+}
+
+impl View {
+    /// Returns the view of the maze centred on a position, as far as the edges of the
+    /// maze allow.
     ///
     /// # Arguments
-    /// * `grid`: The grid to carve the passage in. This is a `HashSet` of `(i32, i32)`
-    ///   coordinates representing the cells in the maze.
-    /// * `x`: The x coordinate of the current cell.
-    /// * `y`: The y coordinate of the current cell.
-    /// * `rng`: The random number generator used to shuffle the directions.
     ///
-    /// This function first checks if the current cell is within the bounds of the maze,
-    /// and if it is not already a part of the maze. If the current cell is valid, then
-    /// the algorithm carves a passage in the current cell and then recursively calls
-    /// itself in the adjacent cell. If the current cell is not valid, then the algorithm
-    /// returns without doing anything.
-    fn carve_passage(grid: &mut HashSet<(i32, i32)>, x: i32, y: i32, rng: &mut impl rand::Rng) {
-        // Insert the current cell into the grid. This is necessary because we don't want
-        // to carve a passage in a cell that is already part of the maze.
-        grid.insert((x, y));
-
-        // Define the four possible directions to move in. These are the four cardinal
-        // directions: up, down, left, and right.
-        let directions = [(0, 2), (2, 0), (0, -2), (-2, 0)];
-
-        // Shuffle the directions randomly. This is necessary because we want to create a
-        // random maze. If we didn't shuffle the directions, we would always carve a
-        // passage in the same direction (up, then right, then down, then left).
-        let mut directions = directions.to_vec();
-        directions.shuffle(rng);
-
-        // Iterate over the shuffled directions.
-        for (dx, dy) in directions {
-            // Calculate the coordinates of the adjacent cell. This is necessary because we
-            // need to know where to carve the passage.
-            let nx = x + dx;
-            let ny = y + dy;
-
-            // Check if the adjacent cell is within the bounds of the maze and if it is not
-            // already a part of the maze. This is necessary because we don't want to carve
-            // a passage in a cell that is outside the bounds of the maze or in a cell that
-            // is already part of the maze.
-            if nx > 0 && nx < MAZE_WIDTH - 1 && ny > 0 && ny < MAZE_HEIGHT - 1 && !grid.contains(&(nx, ny)) {
-                // Check if the cell between the current cell and the adjacent cell is also
-                // within the bounds of the maze. This is necessary because we are using a
-                // recursive backtracking algorithm. If the cell between the current cell and
-                // the adjacent cell is not within the bounds of the maze, then we can't
-                // carve a passage in the adjacent cell.
-                if (y + dy / 2) >= 0 && (y + dy / 2) < MAZE_HEIGHT && (x + dx / 2) >= 0 && (x + dx / 2) < MAZE_WIDTH {
-                    // Insert the cell between the current cell and the adjacent cell into the
-                    // grid. This is necessary because we need to know which cells are part of
-                    // the maze.
-                    grid.insert((x + dx / 2, y + dy / 2));
-                    // Recursively carve a passage in the adjacent cell. This is necessary
-                    // because we need to carve a passage in all cells in the maze.
-                    carve_passage(grid, nx, ny, rng);
-                }
-            }
+    /// * `maze`: The maze being shown.
+    /// * `space`: The number of columns and rows available on the screen.
+    /// * `(x, y)`: The position to centre on, usually the player.
+    pub fn follow(maze: &Maze, space: (i32, i32), (x, y): (i32, i32)) -> View {
+        let columns = maze.width.min(space.0.max(1));
+        let rows = maze.height.min(space.1.max(1));
+        View {
+            x: (x - columns / 2).clamp(0, maze.width - columns),
+            y: (y - rows / 2).clamp(0, maze.height - rows),
+            columns,
+            rows,
         }
     }
 
-    // Start carving passages in the maze. This is necessary because we need to carve a
-    // passage in all cells in the maze.
-    carve_passage(&mut grid, 1, 1, rng);
-    // Return the maze.
-    Maze { grid }
+    /// Maps a position in the maze to its place on the screen.
+    ///
+    /// # Returns
+    ///
+    /// The screen column and row, or `None` if the position is outside the view.
+    pub fn to_screen(&self, (x, y): (i32, i32)) -> Option<(i32, i32)> {
+        let (column, row) = (x - self.x, y - self.y);
+        if (0..self.columns).contains(&column) && (0..self.rows).contains(&row) {
+            Some((column, row))
+        } else {
+            None
+        }
+    }
+}
+
+/// The size of a maze, as picked in the menu or on the command line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MazeSize {
+    /// A fixed width and height, walls included.
+    Fixed(i32, i32),
+    /// As large as the terminal, measured when the maze is generated.
+    FitTerminal,
+}
+
+impl MazeSize {
+    /// Parses a size from a preset name, `fit` or `WIDTHxHEIGHT`.
+    pub fn from_name(name: &str) -> Option<MazeSize> {
+        if name == "fit" {
+            return Some(MazeSize::FitTerminal);
+        }
+        if let Some(&(_, width, height)) = PRESETS.iter().find(|(preset, _, _)| *preset == name) {
+            return Some(MazeSize::Fixed(width, height));
+        }
+        let (width, height) = name.split_once('x')?;
+        Some(MazeSize::Fixed(width.parse().ok()?, height.parse().ok()?))
+    }
+
+    /// Returns the width and height of the maze, normalised by `normalise_size`.
+    ///
+    /// Fitting the terminal leaves the bottom line for the status bar, and needs the
+    /// ncurses library to be initialized.
+    pub fn resolve(self) -> (i32, i32) {
+        match self {
+            MazeSize::Fixed(width, height) => normalise_size(width, height),
+            // Leave the bottom line for the status bar.
+            MazeSize::FitTerminal => normalise_size(COLS(), LINES() - 1),
+        }
+    }
 }
 
-/// Draws the maze to the screen.
+/// Normalises a maze size so the rooms fit it exactly.
+///
+/// The rooms sit on the odd coordinates with walls between and around them, so both
+/// sides must be odd. An even side is shrunk by one, so the maze never outgrows the
+/// space it was given, and sides smaller than one room are grown to fit one.
+pub fn normalise_size(width: i32, height: i32) -> (i32, i32) {
+    let odd = |side: i32| if side % 2 == 1 { side } else { side - 1 };
+    (odd(width.max(MIN_SIZE)), odd(height.max(MIN_SIZE)))
+}
+
+/// Checks that a maze of the given size, once normalised, has at most `MAX_CELLS` cells.
+///
+/// # Returns
+///
+/// A message naming the limit if the maze would be too large.
+pub fn check_size(width: i32, height: i32) -> Result<(), String> {
+    let (width, height) = normalise_size(width, height);
+    if width as usize * height as usize > MAX_CELLS {
+        return Err(format!("A maze can have at most {} cells, not {}x{}.", MAX_CELLS, width, height));
+    }
+    Ok(())
+}
+
+/// Generates a maze with the given algorithm.
+///
+/// The maze is represented as a `Grid` with one bit for each cell, set for the open
+/// cells of the maze. The rooms sit on the odd coordinates, so the outer edge is
+/// always wall, and the cells between connected rooms are opened as passages. The
+/// player starts in the top-left room and the target is in the bottom-right room.
+///
+/// # Arguments
+///
+/// * `generator`: The algorithm that carves the passages.
+/// * `width`: The width of the maze, normalised by `normalise_size`.
+/// * `height`: The height of the maze, normalised by `normalise_size`.
+/// * `shape`: How much to braid the maze and make it sparse once it is carved.
+/// * `rng`: The random number generator the algorithm uses.
+pub fn generate_maze(
+    generator: &dyn MazeGenerator,
+    width: i32,
+    height: i32,
+    shape: &Shape,
+    rng: &mut impl rand::RngCore,
+) -> Maze {
+    let (width, height) = normalise_size(width, height);
+    let mut cells = Cells::new((width - 1) / 2, (height - 1) / 2);
+    generator.carve(&mut cells, rng);
+    shape.apply(&mut cells, rng);
+    Maze { width, height, grid: cells.into_grid(), start: (1, 1), target: (width - 2, height - 2) }
+}
+
+/// Returns the text of part of one row of the maze: a space for each open cell and a
+/// `#` for each wall.
+///
+/// # Arguments
+///
+/// * `maze`: The maze.
+/// * `y`: The row.
+/// * `columns`: The columns of the row to include.
+pub fn row_text(maze: &Maze, y: i32, columns: Range<i32>) -> String {
+    columns.map(|x| if maze.grid.is_open(x, y) { ' ' } else { '#' }).collect()
+}
+
+/// Draws the part of the maze inside the view to the screen.
 ///
 /// # Arguments
 ///
 /// * `maze`: A reference to the maze structure (`&Maze`) that contains the layout of the maze.
+/// * `view`: The part of the maze to draw, which starts at the top-left corner of the screen.
+/// * `fog`: What the player has seen, in the fog of war mode, or `None` to draw everything.
 ///
 /// This function is responsible for drawing the maze to the screen. It does this by
-/// iterating over each cell in the maze and determining whether the cell is a wall
-/// or not. If the cell is not a wall, it will draw a space character in the cell. If
-/// the cell is a wall, it will draw a '#' character in the cell.
-///
-/// The function works by first iterating over each row of the maze. For each row, it
-/// will then iterate over each column in the current row. This is necessary because
-/// we need to draw each cell in the maze.
+/// iterating over each row of the view and building the text of that row with
+/// `row_text`: a space character for each open cell and a '#' character for each
+/// wall.
 ///
-/// For each cell, the function will then determine whether the cell is a wall or not.
-/// It does this by checking if the cell is in the maze's grid. If the cell is in the
-/// grid, it is not a wall. If the cell is not in the grid, it is a wall.
+/// In the fog of war, only the cells in sight are drawn normally. Cells seen before
+/// are drawn dimmed from memory, and cells never seen are left blank.
 ///
-/// Once the function has determined whether the cell is a wall or not, it will then
-/// draw the character in the cell at (x, y) on the screen. If the cell is not a wall,
-/// it will draw a space character. If the cell is a wall, it will draw a '#' character.
+/// Only the rows and columns inside the view are looked at, so drawing a frame costs
+/// the same on a maze with millions of cells as on one that fits the screen.
 ///
 /// This function is necessary because we need to visually represent the maze on the
 /// screen. We need to do this so that the player can see the maze and so that they can
 /// navigate through it.
-pub fn draw_maze(maze: &Maze) -> () {
-    if maze.grid.is_empty() {
-        panic!("The maze is empty and cannot be drawn.");
+pub fn draw_maze(maze: &Maze, view: &View, fog: Option<&Fog>) {
+    // Iterate over each row of the view.
+    for row in 0..view.rows {
+        // Build the text of the row.
+        let y = view.y + row;
+        let text = row_text(maze, y, view.x..view.x + view.columns);
+        let Some(fog) = fog else {
+            // Without fog, draw the whole row on the screen.
+            mvaddstr(row, 0, &text);
+            continue;
+        };
+        // In the fog, draw each cell the player has seen.
+        for (column, ch) in (0..).zip(text.chars()) {
+            match fog.sight(maze, (view.x + column, y)) {
+                Sight::Visible => {
+                    mvaddch(row, column, ch as u32);
+                }
+                Sight::Remembered => {
+                    attron(A_DIM());
+                    mvaddch(row, column, ch as u32);
+                    attroff(A_DIM());
+                }
+                Sight::Unknown => (),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes_are_parsed_from_presets_fit_and_dimensions() {
+        assert_eq!(MazeSize::from_name("medium"), Some(MazeSize::Fixed(31, 15)));
+        assert_eq!(MazeSize::from_name("huge"), Some(MazeSize::Fixed(121, 61)));
+        assert_eq!(MazeSize::from_name("fit"), Some(MazeSize::FitTerminal));
+        assert_eq!(MazeSize::from_name("40x20"), Some(MazeSize::Fixed(40, 20)));
+        for name in ["", "gigantic", "40", "40x", "x20", "40x20x3", "40 x 20", "fortyx20"] {
+            assert_eq!(MazeSize::from_name(name), None, "{}", name);
+        }
     }
-    let mut row: String = String::new();
-    // Iterate over each row of the maze.
-    for y in 0..MAZE_HEIGHT {
-        // Clear the row string.
-        row.clear();
-        // Iterate over each column in the current row.
-        for x in 0..MAZE_WIDTH {
-            // Determine whether the cell at (x, y) is a wall or not.
-            let ch: char = if maze.grid.contains(&(x, y)) { ' ' } else { '#' };
-            // Append the character to the row string.
-            row.push(ch);
+
+    #[test]
+    fn sizes_are_normalised_to_odd_sides_of_at_least_one_room() {
+        assert_eq!(normalise_size(31, 15), (31, 15));
+        // Even sides shrink, so the maze never outgrows its space.
+        assert_eq!(normalise_size(40, 20), (39, 19));
+        assert_eq!(normalise_size(MIN_SIZE, MIN_SIZE), (3, 3));
+        for side in [-7, 0, 1, 2] {
+            assert_eq!(normalise_size(side, side), (MIN_SIZE, MIN_SIZE));
         }
-        // Draw the row string on the screen.
-        mvaddstr(y as i32, 0, row.as_str());
+        assert_eq!(normalise_size(4, 1), (3, 3));
+    }
+
+    #[test]
+    fn sizes_over_the_cell_cap_are_rejected() {
+        assert_eq!(check_size(2001, 2001), Ok(()));
+        // The sides are normalised first, so a 4000x4000 maze is really 3999x3999.
+        assert_eq!(check_size(4000, 4000), Ok(()));
+        assert_eq!(
+            check_size(50000, 50000),
+            Err(format!("A maze can have at most {} cells, not 49999x49999.", MAX_CELLS))
+        );
+        assert!(check_size(i32::MAX, 3).is_err());
+    }
+
+    #[test]
+    fn indices_cover_every_cell_once() {
+        let maze = Maze { width: 5, height: 3, grid: Grid::new(5, 3), start: (1, 1), target: (3, 1) };
+        assert_eq!(maze.cell_count(), 15);
+        let cells = (0..3).flat_map(|y| (0..5).map(move |x| (x, y)));
+        let indices: Vec<usize> = cells.map(|cell| maze.index(cell)).collect();
+        assert_eq!(indices, (0..15).collect::<Vec<_>>());
+        assert!((0..15).all(|i| maze.index(maze.position(i)) == i));
     }
 }
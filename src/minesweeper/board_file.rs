@@ -0,0 +1,272 @@
+use super::topology::{Neighbourhood, Topology};
+use super::utils::{Minefield, Pos};
+use std::fs;
+use std::path::Path;
+
+/// The first word of the header line of every board file.
+const MAGIC: &str = "minesweeper";
+
+/// Converts a minefield to the plain-text board format.
+///
+/// The first line is a header, `minesweeper WIDTH HEIGHT MINES`, followed by one line
+/// per row with one character per cell. Lines starting with `#` are comments. Boards
+/// with a different topology add it to the end of the header, e.g. `hex torus` or
+/// `king layers=3`. The layers of a layered board follow each other, top layer first.
+///
+/// Without the player's state only two characters are used:
+///
+/// * `.` - a safe cell.
+/// * `*` - a mine.
+///
+/// With the player's state, these are also used:
+///
+/// * `0`-`9` - a revealed safe cell, showing its number of adjacent mines.
+/// * `+` - a revealed safe cell with 10 or more adjacent mines, only possible on a
+///   layered board.
+/// * `X` - a revealed mine.
+/// * `F` - a flagged mine, `f` - a flagged safe cell.
+/// * `!` - a mine marked with a question mark, `?` - a safe cell marked with one.
+///
+/// # Arguments
+///
+/// * `minefield`: The minefield to convert.
+/// * `with_state`: Whether to include the revealed cells and the marks.
+pub fn to_text(minefield: &Minefield, with_state: bool) -> String {
+    let mut text = format!(
+        "{} {} {} {}",
+        MAGIC,
+        minefield.width,
+        minefield.height,
+        minefield.mine_count()
+    );
+    if minefield.topology != Topology::default() {
+        text.push_str(&format!(" {}", minefield.topology));
+    }
+    text.push('\n');
+    for y in 0..minefield.height {
+        for x in 0..minefield.width {
+            let pos = Pos::new(x, y);
+            let mine = minefield.is_mine(pos);
+            let ch = match (with_state, mine) {
+                (true, _) if minefield.is_revealed(pos) && mine => 'X',
+                (true, _) if minefield.is_revealed(pos) => {
+                    char::from_digit(minefield.adjacent_mines(pos).into(), 10).unwrap_or('+')
+                }
+                (true, true) if minefield.is_flagged(pos) => 'F',
+                (true, false) if minefield.is_flagged(pos) => 'f',
+                (true, true) if minefield.is_questioned(pos) => '!',
+                (true, false) if minefield.is_questioned(pos) => '?',
+                (_, true) => '*',
+                (_, false) => '.',
+            };
+            text.push(ch);
+        }
+        text.push('\n');
+    }
+    text
+}
+
+/// Parses a minefield from the plain-text board format written by `to_text`.
+///
+/// The number of mines in the header must match the mines in the rows, and every
+/// revealed number must match the mines around it, so a hand-edited file cannot
+/// silently describe a different board.
+///
+/// # Returns
+///
+/// The minefield, or a message describing the first problem found.
+pub fn from_text(text: &str) -> Result<Minefield, String> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim_end()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+    // Parse the header.
+    let (number, header) = lines.next().ok_or("The board file is empty.")?;
+    let fields: Vec<&str> = header.split_whitespace().collect();
+    let (width, height, mines, extra) = match fields.as_slice() {
+        [MAGIC, width, height, mines, extra @ ..] => (
+            width.parse::<usize>().map_err(|e| format!("Line {}: bad width: {}", number, e))?,
+            height.parse::<usize>().map_err(|e| format!("Line {}: bad height: {}", number, e))?,
+            mines.parse::<usize>().map_err(|e| format!("Line {}: bad mine count: {}", number, e))?,
+            extra,
+        ),
+        _ => return Err(format!("Line {}: expected `{} WIDTH HEIGHT MINES`.", number, MAGIC)),
+    };
+    if width == 0 || height == 0 {
+        return Err(format!("Line {}: the board must have at least one cell.", number));
+    }
+    match width.checked_mul(height) {
+        Some(cells) if cells <= super::MAX_CELLS => (),
+        _ => return Err(format!("Line {}: the board can have at most {} cells.", number, super::MAX_CELLS)),
+    }
+
+    // Parse the optional topology after the mine count.
+    let mut topology = Topology::default();
+    for word in extra {
+        match (*word, Neighbourhood::from_name(word), word.strip_prefix("layers=")) {
+            ("torus", _, _) => topology.wrap = true,
+            (_, Some(neighbourhood), _) => topology.neighbourhood = neighbourhood,
+            (_, _, Some(layers)) => {
+                topology.layers = layers.parse().map_err(|e| format!("Line {}: bad layer count: {}", number, e))?;
+            }
+            _ => return Err(format!("Line {}: unknown topology `{}`.", number, word)),
+        }
+    }
+    topology.check(width, height).map_err(|e| format!("Line {}: {}", number, e))?;
+
+    // Read the rows, placing the mines first so the numbers can be checked after.
+    let rows: Vec<(usize, Vec<char>)> = lines.map(|(i, line)| (i, line.chars().collect())).collect();
+    if rows.len() != height {
+        return Err(format!("Expected {} rows but found {}.", height, rows.len()));
+    }
+    let mut minefield = Minefield::new(width, height, topology);
+    for (y, (number, row)) in rows.iter().enumerate() {
+        if row.len() != width {
+            return Err(format!("Line {}: expected {} cells but found {}.", number, width, row.len()));
+        }
+        for (x, &ch) in row.iter().enumerate() {
+            match ch {
+                '*' | 'X' | 'F' | '!' => minefield.place_mine(Pos::new(x, y)),
+                '.' | 'f' | '?' | '+' | '0'..='9' => (),
+                _ => return Err(format!("Line {}: unknown cell `{}` in column {}.", number, ch, x)),
+            }
+        }
+    }
+    if minefield.mine_count() != mines {
+        return Err(format!(
+            "The header says {} mines but the rows contain {}.",
+            mines,
+            minefield.mine_count()
+        ));
+    }
+
+    // Restore the player's state.
+    for (y, (number, row)) in rows.iter().enumerate() {
+        for (x, &ch) in row.iter().enumerate() {
+            let pos = Pos::new(x, y);
+            if let Some(count) = ch.to_digit(10) {
+                if count != u32::from(minefield.adjacent_mines(pos)) {
+                    return Err(format!(
+                        "Line {}: column {} shows {} but has {} adjacent mines.",
+                        number,
+                        x,
+                        count,
+                        minefield.adjacent_mines(pos)
+                    ));
+                }
+            }
+            if ch == '+' && minefield.adjacent_mines(pos) < 10 {
+                return Err(format!(
+                    "Line {}: column {} shows 10 or more but has {} adjacent mines.",
+                    number,
+                    x,
+                    minefield.adjacent_mines(pos)
+                ));
+            }
+            let revealed = ch == 'X' || ch == '+' || ch.is_ascii_digit();
+            let flagged = ch == 'F' || ch == 'f';
+            let questioned = ch == '!' || ch == '?';
+            minefield.restore_cell(pos, revealed, flagged, questioned);
+        }
+    }
+
+    Ok(minefield)
+}
+
+/// Saves a minefield to a board file.
+pub fn save(minefield: &Minefield, with_state: bool, path: &Path) -> Result<(), String> {
+    fs::write(path, to_text(minefield, with_state))
+        .map_err(|e| format!("Could not write {}: {}", path.display(), e))
+}
+
+/// Loads a minefield from a board file.
+pub fn load(path: &Path) -> Result<Minefield, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    from_text(&text).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::minesweeper::topology::Neighbourhood;
+    use crate::minesweeper::utils::Action;
+
+    /// Returns a board of the given topology with a reveal, a flag and a question
+    /// mark played on it.
+    fn played(topology: Topology) -> Minefield {
+        let mut minefield = Minefield::new(8, 6, topology);
+        for (x, y) in [(0, 0), (3, 1), (6, 2), (1, 4), (7, 5)] {
+            minefield.place_mine(Pos::new(x, y));
+        }
+        minefield.apply(Action::Reveal(Pos::new(4, 4)));
+        minefield.apply(Action::Mark(Pos::new(0, 0)));
+        minefield.apply(Action::Mark(Pos::new(7, 0)));
+        minefield.apply(Action::Mark(Pos::new(7, 0)));
+        minefield
+    }
+
+    #[test]
+    fn a_board_with_the_players_state_round_trips() {
+        let text = "minesweeper 5 3 3\n\
+                    01*F.\n\
+                    012?!\n\
+                    00011\n";
+        let minefield = from_text(text).unwrap();
+        assert!(minefield.is_flagged(Pos::new(3, 0)) && minefield.is_mine(Pos::new(3, 0)));
+        assert!(minefield.is_questioned(Pos::new(4, 1)) && minefield.is_mine(Pos::new(4, 1)));
+        assert!(minefield.is_revealed(Pos::new(0, 2)) && !minefield.is_revealed(Pos::new(4, 0)));
+        assert_eq!(to_text(&minefield, true), text);
+    }
+
+    #[test]
+    fn a_played_board_on_every_topology_round_trips() {
+        let topologies = [
+            Topology::default(),
+            Topology { neighbourhood: Neighbourhood::Hex, wrap: true, layers: 1 },
+            Topology { neighbourhood: Neighbourhood::Knight, wrap: false, layers: 1 },
+            Topology { neighbourhood: Neighbourhood::Orthogonal, wrap: true, layers: 1 },
+            Topology { neighbourhood: Neighbourhood::King, wrap: false, layers: 3 },
+        ];
+        for topology in topologies {
+            let minefield = played(topology);
+            for with_state in [false, true] {
+                let text = to_text(&minefield, with_state);
+                let loaded = from_text(&text).unwrap();
+                assert_eq!(loaded.topology, topology);
+                assert_eq!(to_text(&loaded, with_state), text);
+            }
+        }
+    }
+
+    #[test]
+    fn comments_and_blank_lines_are_skipped() {
+        let minefield = from_text("# A corner mine.\nminesweeper 2 2 1\n\n*.\n# The last row.\n..\n").unwrap();
+        assert_eq!(minefield.mine_count(), 1);
+        assert!(minefield.is_mine(Pos::new(0, 0)));
+    }
+
+    #[test]
+    fn a_board_that_contradicts_itself_is_rejected() {
+        let errors = [
+            ("", "The board file is empty."),
+            ("sweeper 2 2 1\n*.\n..\n", "Line 1: expected `minesweeper WIDTH HEIGHT MINES`."),
+            ("minesweeper 2 2 2\n*.\n..\n", "The header says 2 mines but the rows contain 1."),
+            ("minesweeper 2 2 1\n*.\n", "Expected 2 rows but found 1."),
+            ("minesweeper 2 2 1\n*.\n...\n", "Line 3: expected 2 cells but found 3."),
+            ("minesweeper 2 2 1\n*.\n.x\n", "Line 3: unknown cell `x` in column 1."),
+            ("minesweeper 2 2 1\n*.\n.2\n", "Line 3: column 1 shows 2 but has 1 adjacent mines."),
+            ("minesweeper 2 2 1 square\n*.\n..\n", "Line 1: unknown topology `square`."),
+        ];
+        for (text, error) in errors {
+            assert_eq!(from_text(text).err().as_deref(), Some(error), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn a_board_over_the_cell_cap_is_rejected_before_it_is_allocated() {
+        let error = from_text(&format!("minesweeper {} 2 1\n", usize::MAX)).err().unwrap();
+        assert_eq!(error, format!("Line 1: the board can have at most {} cells.", crate::minesweeper::MAX_CELLS));
+    }
+}
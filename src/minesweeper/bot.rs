@@ -0,0 +1,176 @@
+use super::solver;
+use super::topology::Topology;
+use super::utils::{self, Action, Minefield, Pos};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::time::Instant;
+
+/// How the autoplay bot chooses its moves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Strategy {
+    /// Reveal every cell the full solver proves safe, and otherwise guess the cell
+    /// least likely to be a mine.
+    Probability,
+    /// Reveal only the cells a single number proves safe, and otherwise guess a
+    /// random hidden cell that is not known to be a mine.
+    Simple,
+}
+
+impl Strategy {
+    /// Returns the name used for the strategy on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            Strategy::Probability => "probability",
+            Strategy::Simple => "simple",
+        }
+    }
+
+    /// Parses a strategy from its name.
+    pub fn from_name(name: &str) -> Option<Strategy> {
+        [Strategy::Probability, Strategy::Simple].into_iter().find(|s| s.name() == name)
+    }
+}
+
+/// The outcome of one game played by the bot.
+pub struct Outcome {
+    /// Whether the bot cleared the board.
+    pub won: bool,
+    /// The 3BV of the board.
+    pub three_bv: u32,
+    /// The number of reveals that were not proven safe.
+    pub guesses: u32,
+    /// The time the bot took, in seconds.
+    pub seconds: f64,
+}
+
+/// Plays a whole game on the minefield without any user interface.
+///
+/// # Arguments
+///
+/// * `minefield`: The board to play on, which is left in its final state.
+/// * `strategy`: How to choose the moves.
+/// * `seed`: The seed for the random guesses of the simple strategy.
+pub fn play(minefield: &mut Minefield, strategy: Strategy, seed: u64) -> Outcome {
+    let three_bv = minefield.three_bv();
+    let started = Instant::now();
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut guesses = 0;
+
+    let won = loop {
+        if minefield.is_won() {
+            break true;
+        }
+
+        // Reveal every proven safe cell before looking at the board again.
+        let (deductions, guess) = match strategy {
+            Strategy::Probability => {
+                let analysis = solver::analyse(minefield);
+                let guess = analysis.best_guess();
+                (analysis.deductions, guess)
+            }
+            Strategy::Simple => {
+                let deductions = solver::trivial(minefield);
+                let hidden: Vec<Pos> = minefield
+                    .positions()
+                    .filter(|&p| !minefield.is_revealed(p) && !deductions.iter().any(|d| d.mine && d.pos == p))
+                    .collect();
+                let guess = hidden.choose(&mut rng).copied();
+                (deductions, guess)
+            }
+        };
+        let safe: Vec<Pos> = deductions.iter().filter(|d| !d.mine).map(|d| d.pos).collect();
+        if !safe.is_empty() {
+            // A proven safe cell can only be a mine if the board was edited by hand
+            // into one that contradicts its numbers.
+            if safe.into_iter().any(|pos| minefield.apply(Action::Reveal(pos))) {
+                break false;
+            }
+            continue;
+        }
+
+        // Nothing is certain, so take the guess.
+        let Some(pos) = guess else {
+            break false;
+        };
+        guesses += 1;
+        if minefield.apply(Action::Reveal(pos)) {
+            break false;
+        }
+    };
+
+    Outcome { won, three_bv, guesses, seconds: started.elapsed().as_secs_f64() }
+}
+
+/// The combined results of the games played on one difficulty.
+pub struct Summary {
+    /// The difficulty label, as used in the records.
+    pub difficulty: String,
+    /// The number of games played.
+    pub games: u32,
+    /// The number of games won.
+    pub wins: u32,
+    /// The total guesses over all games.
+    pub guesses: u32,
+    /// The total 3BV of the won games.
+    pub won_three_bv: u32,
+    /// The total time of the won games, in seconds.
+    pub won_seconds: f64,
+}
+
+impl Summary {
+    /// Returns the share of games won, as a percentage.
+    pub fn win_rate(&self) -> f64 {
+        100.0 * self.wins as f64 / self.games.max(1) as f64
+    }
+
+    /// Returns the 3BV solved per second over the won games.
+    pub fn three_bv_per_second(&self) -> f64 {
+        if self.won_seconds > 0.0 {
+            self.won_three_bv as f64 / self.won_seconds
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns the average number of guesses per game.
+    pub fn guesses_per_game(&self) -> f64 {
+        self.guesses as f64 / self.games.max(1) as f64
+    }
+}
+
+/// Plays `games` seeded games on a board of the given size and sums up the results.
+///
+/// Game `i` is played on the board generated from `seed + i`, so two runs with the
+/// same seed play exactly the same boards, and a change to the generator or the
+/// strategy can be measured on its own.
+pub fn benchmark(
+    (width, height, mines): (usize, usize, usize),
+    topology: Topology,
+    strategy: Strategy,
+    games: u32,
+    seed: u64,
+) -> Summary {
+    let mut summary = Summary {
+        difficulty: super::records::difficulty_label(width, height, mines, topology),
+        games,
+        wins: 0,
+        guesses: 0,
+        won_three_bv: 0,
+        won_seconds: 0.0,
+    };
+    for i in 0..u64::from(games) {
+        let seed = seed.wrapping_add(i);
+        let mut minefield = utils::generate_seeded_minefield(width, height * topology.layers, mines, topology, seed);
+        // The guesses must not reuse the stream that placed the mines, or the first
+        // random guess would always pick the first mine placed.
+        let outcome = play(&mut minefield, strategy, !seed);
+        summary.guesses += outcome.guesses;
+        if outcome.won {
+            summary.wins += 1;
+            summary.won_three_bv += outcome.three_bv;
+            summary.won_seconds += outcome.seconds;
+        }
+    }
+    summary
+}
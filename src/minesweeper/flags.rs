@@ -0,0 +1,253 @@
+use super::utils::{Action, Minefield, Pos};
+use super::viewport::Viewport;
+use super::{
+    draw_game_state, draw_layer_strip, draw_rulers, end_screen, footer_row, move_cursor, read_action,
+    screen_position, screen_space, start_screen, MouseButtons,
+};
+use ncurses::*;
+use std::collections::HashMap;
+
+/// The names of the two players, shown on the scoreboard.
+const PLAYER_NAMES: [&str; 2] = ["Blue", "Yellow"];
+/// The colour pair of the mines claimed by each player.
+const PLAYER_COLORS: [i16; 2] = [11, 12];
+
+/// The score of a game of Minesweeper Flags: who claimed which mine and whose turn it is.
+struct Claims {
+    /// The player who claimed each mine found so far.
+    owners: HashMap<Pos, usize>,
+    /// The mines claimed by each player.
+    scores: [usize; 2],
+    /// The player whose turn it is.
+    turn: usize,
+    /// The number of mines on the board.
+    mines: usize,
+}
+
+impl Claims {
+    /// Creates the score of a new game on a board with `mines` mines, with the first player to move.
+    fn new(mines: usize) -> Claims {
+        Claims { owners: HashMap::new(), scores: [0; 2], turn: 0, mines }
+    }
+
+    /// Reveals a cell for the player whose turn it is.
+    ///
+    /// Revealing a mine claims it and the player goes again; revealing a safe cell passes
+    /// the turn. Cells that are already revealed or marked cannot be taken.
+    ///
+    /// # Returns
+    /// The message describing what happened, or `None` if the cell cannot be taken.
+    fn reveal(&mut self, minefield: &mut Minefield, pos: Pos) -> Option<String> {
+        if minefield.is_revealed(pos) || minefield.is_flagged(pos) || minefield.is_questioned(pos) {
+            return None;
+        }
+        if minefield.apply(Action::Reveal(pos)) {
+            self.owners.insert(pos, self.turn);
+            self.scores[self.turn] += 1;
+            Some(format!("{} claims a mine and goes again.", PLAYER_NAMES[self.turn]))
+        } else {
+            self.turn = 1 - self.turn;
+            Some(format!("{}'s turn.", PLAYER_NAMES[self.turn]))
+        }
+    }
+
+    /// Returns the player who has claimed more than half of the mines, if any.
+    fn winner(&self) -> Option<usize> {
+        (0..2).find(|&player| self.scores[player] * 2 > self.mines)
+    }
+
+    /// Returns whether the game is over, i.e. a player has a majority of the mines or
+    /// every mine has been claimed with an even split.
+    fn is_over(&self) -> bool {
+        self.winner().is_some() || self.scores[0] + self.scores[1] == self.mines
+    }
+}
+
+/// Draws the scoreboard with each player's claimed mines, marking whose turn it is.
+///
+/// # Arguments
+///
+/// * `scores`: The mines claimed by each player.
+/// * `turn`: The player whose turn it is.
+/// * `mines`: The number of mines on the board.
+fn draw_scoreboard(scores: [usize; 2], turn: usize, mines: usize) {
+    mv(0, 0);
+    for (player, name) in PLAYER_NAMES.iter().enumerate() {
+        // The player whose turn it is is marked with an arrow.
+        addstr(if player == turn { "> " } else { "  " });
+        attron(COLOR_PAIR(PLAYER_COLORS[player]));
+        addstr(&format!(" {}: {} ", name, scores[player]));
+        attroff(COLOR_PAIR(PLAYER_COLORS[player]));
+        addstr("  ");
+    }
+    let claimed = scores[0] + scores[1];
+    addstr(&format!("Mines left: {}  To win: {}", mines - claimed, mines / 2 + 1));
+}
+
+/// Draws the claimed mines in the colour of the player who found them, over the
+/// board drawn by `draw_game_state`, leaving the cell under the cursor alone.
+fn draw_claims(minefield: &Minefield, viewport: &Viewport, owners: &HashMap<Pos, usize>, cursor: Option<Pos>) {
+    for (&pos, &player) in owners {
+        let visible = (viewport.x..viewport.x + viewport.columns).contains(&pos.x)
+            && (viewport.y..viewport.y + viewport.rows).contains(&pos.y);
+        if !visible || Some(pos) == cursor {
+            continue;
+        }
+        let (row, column) = screen_position(minefield, viewport, pos);
+        attron(COLOR_PAIR(PLAYER_COLORS[player]));
+        mvaddch(row, column, '*' as u32);
+        attroff(COLOR_PAIR(PLAYER_COLORS[player]));
+    }
+}
+
+/// Plays a hot-seat game of Minesweeper Flags for two players on one board.
+///
+/// The players take turns revealing cells. Revealing a mine claims it: the player
+/// scores a point and goes again. Revealing a safe cell opens it as usual and passes
+/// the turn. The first player to claim more than half of the mines wins, so boards
+/// with an odd number of mines can never end in a draw.
+///
+/// # Arguments
+/// * `minefield`: The minefield to play on.
+pub fn play(mut minefield: Minefield) {
+    let mines = minefield.mine_count();
+
+    start_screen();
+
+    // Initialize the position of the cursor.
+    let mut cursor = Pos::new(0, 0);
+    // The part of the board shown on the screen, which follows the cursor.
+    let mut viewport = Viewport::new();
+    // The mouse buttons currently held.
+    let mut buttons = MouseButtons { left: false, right: false, chorded: false };
+    // The mines claimed by each player, and whose turn it is.
+    let mut claims = Claims::new(mines);
+    // A message shown below the board, such as who claimed the last mine.
+    let mut message = String::new();
+
+    loop {
+        // Clear the screen without forcing a full repaint.
+        erase();
+
+        // Scroll the viewport to follow the cursor, then draw the scoreboard and the board.
+        viewport.follow((minefield.width, minefield.layer_height()), screen_space(&minefield), cursor);
+        draw_scoreboard(claims.scores, claims.turn, mines);
+        draw_rulers(&minefield, &viewport);
+        draw_game_state(&minefield, Some(cursor), &viewport, false);
+        draw_claims(&minefield, &viewport, &claims.owners, Some(cursor));
+        draw_layer_strip(&minefield, cursor, &viewport, false);
+        mvaddstr(footer_row(&minefield, &viewport), 0, &message);
+
+        // Refresh the screen.
+        refresh();
+
+        // Read the input from the user.
+        let input = getch();
+
+        // If the user pressed q, break the loop.
+        if input == 'q' as i32 {
+            break;
+        }
+
+        // Only revealing a hidden cell takes a turn. Chording could set off mines the
+        // player never chose, so it is not allowed, and neither are marks.
+        if let Some(Action::Reveal(pos)) = read_action(&minefield, input, &viewport, &mut cursor, &mut buttons) {
+            if let Some(outcome) = claims.reveal(&mut minefield, pos) {
+                message = outcome;
+            }
+        }
+
+        // Move the cursor based on the input.
+        move_cursor(&minefield, input, &viewport, &mut cursor);
+
+        // The game ends once a player has more than half of the mines, or every mine
+        // has been claimed with an even split.
+        if claims.is_over() {
+            let scores = claims.scores;
+            erase();
+            draw_scoreboard(claims.scores, claims.turn, mines);
+            draw_rulers(&minefield, &viewport);
+            draw_game_state(&minefield, None, &viewport, true);
+            draw_claims(&minefield, &viewport, &claims.owners, None);
+            let result = match claims.winner() {
+                Some(player) => format!("{} wins {} to {}!", PLAYER_NAMES[player], scores[player], scores[1 - player]),
+                None => format!("It's a draw, {} all!", scores[0]),
+            };
+            mvaddstr(footer_row(&minefield, &viewport), 0, &format!("{} Press any key to quit.", result));
+            refresh();
+
+            // Wait for a key without the usual timeout, so both players see the result.
+            timeout(-1);
+            getch();
+            break;
+        }
+    }
+
+    // Stop reporting mouse events and clean up the ncurses library.
+    end_screen();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::minesweeper::board_file;
+
+    /// A board with five mines along the top row and nothing but open ground below.
+    const BOARD: &str = "minesweeper 5 3 5\n*****\n.....\n.....\n";
+
+    #[test]
+    fn a_found_mine_is_claimed_and_keeps_the_turn() {
+        let mut minefield = board_file::from_text(BOARD).unwrap();
+        let mut claims = Claims::new(5);
+        assert_eq!(claims.reveal(&mut minefield, Pos::new(0, 0)).unwrap(), "Blue claims a mine and goes again.");
+        assert_eq!(claims.turn, 0);
+        assert_eq!(claims.scores, [1, 0]);
+        assert_eq!(claims.owners.get(&Pos::new(0, 0)), Some(&0));
+        // A claimed mine cannot be taken again.
+        assert_eq!(claims.reveal(&mut minefield, Pos::new(0, 0)), None);
+        assert_eq!(claims.scores, [1, 0]);
+    }
+
+    #[test]
+    fn a_safe_cell_passes_the_turn() {
+        let mut minefield = board_file::from_text(BOARD).unwrap();
+        let mut claims = Claims::new(5);
+        // The open ground floods, but it is still just one turn.
+        assert_eq!(claims.reveal(&mut minefield, Pos::new(2, 2)).unwrap(), "Yellow's turn.");
+        assert_eq!(claims.turn, 1);
+        assert!(minefield.is_revealed(Pos::new(4, 1)));
+        assert_eq!(claims.reveal(&mut minefield, Pos::new(3, 0)).unwrap(), "Yellow claims a mine and goes again.");
+        assert_eq!(claims.scores, [0, 1]);
+        assert_eq!(claims.owners.get(&Pos::new(3, 0)), Some(&1));
+        // Revealed and marked cells cannot be taken.
+        assert_eq!(claims.reveal(&mut minefield, Pos::new(0, 1)), None);
+        minefield.apply(Action::Mark(Pos::new(4, 0)));
+        assert_eq!(claims.reveal(&mut minefield, Pos::new(4, 0)), None);
+        assert_eq!(claims.turn, 1);
+    }
+
+    #[test]
+    fn the_game_ends_at_a_majority() {
+        let mut minefield = board_file::from_text(BOARD).unwrap();
+        let mut claims = Claims::new(5);
+        for x in 0..2 {
+            claims.reveal(&mut minefield, Pos::new(x, 0));
+            assert!(!claims.is_over());
+        }
+        claims.reveal(&mut minefield, Pos::new(2, 0));
+        assert_eq!(claims.scores, [3, 0]);
+        assert_eq!(claims.winner(), Some(0));
+        assert!(claims.is_over());
+    }
+
+    #[test]
+    fn an_even_split_is_a_draw() {
+        let mut claims = Claims::new(4);
+        claims.scores = [2, 1];
+        assert_eq!(claims.winner(), None);
+        assert!(!claims.is_over());
+        claims.scores = [2, 2];
+        assert_eq!(claims.winner(), None);
+        assert!(claims.is_over());
+    }
+}
@@ -0,0 +1,216 @@
+use super::solver;
+use super::utils::{Action, Minefield, Pos};
+
+/// The largest board, in cells, whose decisions are analysed. The solver runs on the
+/// whole board for every decision, so the time grows with the square of the board:
+/// an expert board takes a fraction of a second, but a 100x100 board over a minute.
+pub const MAX_CELLS: usize = 1_000;
+
+/// How a decision looked from the player's point of view when it was made.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    /// Every cell revealed was proven safe by the numbers on the board.
+    Forced,
+    /// Nothing on the board was proven safe, so some risk could not be avoided.
+    Guess,
+    /// A risk taken while a proven safe cell was available, or a proven mine revealed.
+    Mistake,
+}
+
+/// One reveal or chord of the game, with how risky it was.
+#[derive(Clone, Debug)]
+pub struct Decision {
+    /// The action the player made.
+    pub action: Action,
+    /// Whether the action was forced, a guess or a mistake.
+    pub verdict: Verdict,
+    /// The chance, when the action was made, that it would not set off a mine.
+    pub survival: f64,
+    /// The best chance of not setting off a mine that any single reveal offered at
+    /// that moment.
+    pub best_survival: f64,
+    /// Whether the action did not set off a mine.
+    pub survived: bool,
+}
+
+impl Decision {
+    /// Returns how good the decision was, from 0 to 1: forced moves score 1, guesses
+    /// score their chance of survival compared with the best guess available, and
+    /// mistakes score 0.
+    fn skill(&self) -> f64 {
+        match self.verdict {
+            Verdict::Forced => 1.0,
+            Verdict::Guess if self.best_survival > 0.0 => (self.survival / self.best_survival).min(1.0),
+            Verdict::Guess | Verdict::Mistake => 0.0,
+        }
+    }
+}
+
+/// The analysis of every decision of a finished game.
+pub struct Report {
+    /// The reveals and chords of the game, in order. Marks are left out.
+    pub decisions: Vec<Decision>,
+}
+
+impl Report {
+    /// Returns the number of decisions with the given verdict.
+    pub fn count(&self, verdict: Verdict) -> usize {
+        self.decisions.iter().filter(|d| d.verdict == verdict).count()
+    }
+
+    /// Returns the luck the game needed, as a percentage: the chance that the risks
+    /// the player survived would have set off a mine. 0% means the game never
+    /// depended on luck.
+    pub fn luck(&self) -> f64 {
+        let survived: f64 = self
+            .decisions
+            .iter()
+            .filter(|d| d.survived && d.verdict != Verdict::Forced)
+            .map(|d| d.survival)
+            .product();
+        100.0 * (1.0 - survived)
+    }
+
+    /// Returns the skill score as a percentage: the average of how good each
+    /// decision was, so a game of forced moves and best guesses scores 100%.
+    pub fn skill(&self) -> f64 {
+        if self.decisions.is_empty() {
+            return 100.0;
+        }
+        100.0 * self.decisions.iter().map(Decision::skill).sum::<f64>() / self.decisions.len() as f64
+    }
+}
+
+/// Returns the hidden cells an action would reveal on the board as it is.
+fn revealed_by(minefield: &Minefield, action: Action) -> Vec<Pos> {
+    let hidden = |p: &Pos| !minefield.is_revealed(*p) && !minefield.is_flagged(*p);
+    match action {
+        Action::Reveal(pos) => vec![pos].into_iter().filter(hidden).collect(),
+        Action::Chord(pos) => minefield.neighbours(pos).filter(hidden).collect(),
+        Action::Mark(_) => Vec::new(),
+    }
+}
+
+/// Replays a finished game and judges every reveal and chord, using the solver on
+/// the board exactly as the player saw it before each one.
+///
+/// Only the actions still in the undo history are replayed, so moves that were
+/// undone are left out of the analysis. Moves made by the assists are replayed but
+/// not judged, since the player did not choose them.
+///
+/// # Arguments
+///
+/// * `minefield`: The board at the end of the game.
+///
+/// # Returns
+///
+/// The report, or `None` if the board has more than `MAX_CELLS` cells.
+pub fn analyse(minefield: &Minefield) -> Option<Report> {
+    if minefield.width * minefield.height > MAX_CELLS {
+        return None;
+    }
+    let (mut board, actions) = minefield.rewind();
+    let mut decisions = Vec::new();
+
+    for (action, assisted) in actions {
+        let cells = revealed_by(&board, action);
+        if !cells.is_empty() && !assisted {
+            let analysis = solver::analyse(&board);
+            // A chord survives only if none of its cells is a mine. The chances of the
+            // cells are not independent, so this is an estimate when it is not certain.
+            let survival: f64 = cells.iter().map(|&p| 1.0 - analysis.mine_probability(p)).product();
+            let best_survival = if analysis.has_safe() {
+                1.0
+            } else {
+                analysis.best_guess().map_or(1.0, |p| 1.0 - analysis.mine_probability(p))
+            };
+            let verdict = if survival >= 1.0 {
+                Verdict::Forced
+            } else if survival <= 0.0 || analysis.has_safe() {
+                Verdict::Mistake
+            } else {
+                Verdict::Guess
+            };
+            let survived = !board.apply(action);
+            decisions.push(Decision { action, verdict, survival, best_survival, survived });
+        } else {
+            board.apply(action);
+        }
+    }
+
+    Some(Report { decisions })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::minesweeper::board_file;
+
+    /// A strip where the 1 proves the mine on its left and the 0 proves the cell on its
+    /// right safe, while the last two cells share the other mine.
+    const STRIP: &str = "minesweeper 6 1 2\n*10..*\n";
+
+    /// Plays `actions` on the board in `text` and analyses the game.
+    fn analysed(text: &str, actions: &[Action]) -> Report {
+        let mut minefield = board_file::from_text(text).unwrap();
+        for &action in actions {
+            minefield.apply(action);
+        }
+        analyse(&minefield).unwrap()
+    }
+
+    #[test]
+    fn revealing_a_proven_safe_cell_is_forced() {
+        let report = analysed(STRIP, &[Action::Reveal(Pos::new(3, 0))]);
+        assert_eq!(report.decisions.len(), 1);
+        let decision = &report.decisions[0];
+        assert_eq!(decision.verdict, Verdict::Forced);
+        assert_eq!((decision.survival, decision.survived), (1.0, true));
+        assert_eq!((report.luck(), report.skill()), (0.0, 100.0));
+    }
+
+    #[test]
+    fn a_reveal_with_nothing_proven_is_a_guess() {
+        let report = analysed("minesweeper 3 1 1\n.*.\n", &[Action::Reveal(Pos::new(0, 0))]);
+        let decision = &report.decisions[0];
+        assert_eq!(decision.verdict, Verdict::Guess);
+        assert!((decision.survival - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(decision.best_survival, decision.survival);
+        assert!(decision.survived);
+        // The best guess there was needed a third of luck, but was played perfectly.
+        assert!((report.luck() - 100.0 / 3.0).abs() < 1e-9);
+        assert_eq!(report.skill(), 100.0);
+    }
+
+    #[test]
+    fn a_risk_taken_next_to_a_safe_cell_is_a_mistake() {
+        let report = analysed(STRIP, &[Action::Reveal(Pos::new(4, 0))]);
+        let decision = &report.decisions[0];
+        assert_eq!(decision.verdict, Verdict::Mistake);
+        assert_eq!(decision.survival, 0.5);
+        assert_eq!(decision.best_survival, 1.0);
+        assert_eq!(report.skill(), 0.0);
+
+        // So is revealing a proven mine.
+        let report = analysed(STRIP, &[Action::Reveal(Pos::new(0, 0))]);
+        let decision = &report.decisions[0];
+        assert_eq!(decision.verdict, Verdict::Mistake);
+        assert_eq!((decision.survival, decision.survived), (0.0, false));
+    }
+
+    #[test]
+    fn marks_and_assist_moves_are_not_judged() {
+        let mut minefield = board_file::from_text(STRIP).unwrap();
+        minefield.apply(Action::Mark(Pos::new(5, 0)));
+        minefield.apply_assist(Action::Reveal(Pos::new(3, 0)));
+        minefield.apply(Action::Mark(Pos::new(5, 0)));
+        minefield.apply(Action::Mark(Pos::new(5, 0)));
+        assert!(analyse(&minefield).unwrap().decisions.is_empty());
+    }
+
+    #[test]
+    fn boards_over_the_cap_are_not_analysed() {
+        let text = format!("minesweeper {} 1 0\n{}\n", MAX_CELLS + 1, ".".repeat(MAX_CELLS + 1));
+        assert!(analyse(&board_file::from_text(&text).unwrap()).is_none());
+    }
+}
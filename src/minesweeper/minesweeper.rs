@@ -2,98 +2,703 @@ extern crate ncurses;
 extern crate rand;
 use ncurses::*;
 use std::cmp::min;
-use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+mod board_file;
+mod bot;
+mod flags;
+mod luck;
+mod records;
+mod solver;
+mod topology;
+mod tutorial;
 mod utils;
+mod viewport;
+pub use bot::Strategy;
+pub use topology::{Neighbourhood, Topology};
+use utils::{Action, Pos};
+use viewport::Viewport;
 
-const WIDTH: usize = 10;
-const HEIGHT: usize = 10;
+/// The screen row at which the board starts, below the status bar and the ruler.
+const BOARD_ROW: i32 = 2;
+/// The number of screen rows kept free below the board for messages and the end of
+/// game summary.
+const FOOTER_ROWS: i32 = 6;
+/// The number of screen columns kept free to the right of a layered board for the
+/// strip summarising the layers above and below.
+const STRIP_WIDTH: i32 = 14;
+
+/// The largest board, in cells over all its layers, that can be played. A board of
+/// 1000x1000 fits four times over, and anything larger would take gigabytes.
+pub const MAX_CELLS: usize = 4_000_000;
+
+/// The optional rules of a game of Minesweeper.
+#[derive(Clone, Copy, Debug)]
+pub struct Options {
+    /// The number of mines that can be set off before the game is lost. Every mine
+    /// set off costs a life and stays on the board, marked. The classic game has one.
+    pub lives: u32,
+    /// Whether to flag every hidden cell around a number that has exactly as many
+    /// hidden neighbours as its count. Can be toggled during the game.
+    pub auto_flag: bool,
+    /// Whether to open the other hidden cells around a number once it has as many
+    /// flags as its count, as a chord would. Can be toggled during the game.
+    pub auto_open: bool,
+}
+
+impl Default for Options {
+    /// The classic rules, where the first mine ends the game, without assists.
+    fn default() -> Options {
+        Options { lives: 1, auto_flag: false, auto_open: false }
+    }
+}
+
+/// The live statistics of a game, shown in the status bar.
+struct Stats {
+    /// The moment of the first click, which starts the timer.
+    started: Option<Instant>,
+    /// The moment the game ended, which stops the timer, so the time spent on the
+    /// end screen and its analysis is not counted.
+    stopped: Option<Instant>,
+    /// Every action made on the board, in order, whether from the keyboard or the mouse.
+    actions: Vec<Action>,
+    /// Whether a mine hit has been undone, which turns the game into a practice game
+    /// that is kept out of the records.
+    practice: bool,
+    /// The number of lives the game started with.
+    lives: u32,
+    /// Whether the auto-flag assist is switched on.
+    auto_flag: bool,
+    /// Whether the auto-open assist is switched on.
+    auto_open: bool,
+    /// Whether an assist has changed the board, which keeps the game out of the
+    /// records like a practice game.
+    assisted: bool,
+}
+
+impl Stats {
+    /// Creates the statistics of a game that has not started yet, with the given rules.
+    fn new(options: Options) -> Stats {
+        Stats {
+            started: None,
+            stopped: None,
+            actions: Vec::new(),
+            practice: false,
+            lives: options.lives.max(1),
+            auto_flag: options.auto_flag,
+            auto_open: options.auto_open,
+            assisted: false,
+        }
+    }
+
+    /// Returns the number of lives left, one less for every mine set off.
+    fn lives_left(&self, minefield: &utils::Minefield) -> u32 {
+        minefield.lives_left(self.lives)
+    }
+
+    /// Records an action on the board, starting the timer on the first one.
+    fn record(&mut self, action: Action) {
+        self.started.get_or_insert_with(Instant::now);
+        self.actions.push(action);
+    }
+
+    /// Returns the number of clicks made, one per recorded action.
+    fn clicks(&self) -> u32 {
+        self.actions.len() as u32
+    }
+
+    /// Returns the number of seconds from the first click until now, or until the
+    /// timer was stopped.
+    fn elapsed(&self) -> f64 {
+        let now = self.stopped.unwrap_or_else(Instant::now);
+        self.started.map_or(0.0, |started| now.saturating_duration_since(started).as_secs_f64())
+    }
+
+    /// Stops the timer at the end of the game.
+    fn stop(&mut self) {
+        self.stopped.get_or_insert_with(Instant::now);
+    }
+
+    /// Starts the timer again after a mine hit was undone, leaving out the time it
+    /// was stopped for.
+    fn resume(&mut self) {
+        if let (Some(started), Some(stopped)) = (&mut self.started, self.stopped.take()) {
+            *started += stopped.elapsed();
+        }
+    }
+}
+
+/// Draws the status bar with the elapsed time, the mines remaining and the clicks made.
+///
+/// # Arguments
+///
+/// * `stats`: The live statistics of the game.
+/// * `minefield`: A reference to the game's minefield.
+/// * `cursor`: The position of the cursor.
+fn draw_status_bar(stats: &Stats, minefield: &utils::Minefield, cursor: Pos) {
+    // The mines remaining goes negative when the player places too many flags.
+    let remaining = minefield.mine_count() as i64 - minefield.flag_count() as i64;
+    mvprintw(
+        0,
+        0,
+        &format!(
+            "Time: {:>3}  Mines: {:>3}  Clicks: {}{}  ({}, {}){}{}{}",
+            stats.elapsed() as u64,
+            remaining,
+            stats.clicks(),
+            if stats.lives > 1 {
+                format!("  Lives: {}/{}", stats.lives_left(minefield), stats.lives)
+            } else {
+                String::new()
+            },
+            cursor.x,
+            cursor.y % minefield.layer_height(),
+            if minefield.topology.layers > 1 {
+                format!("  Layer: {}/{}", cursor.y / minefield.layer_height() + 1, minefield.topology.layers)
+            } else {
+                String::new()
+            },
+            match (stats.auto_flag, stats.auto_open) {
+                (true, true) => "  Auto: flag+open",
+                (true, false) => "  Auto: flag",
+                (false, true) => "  Auto: open",
+                (false, false) => "",
+            },
+            if stats.practice { "  PRACTICE" } else { "" },
+        ),
+    );
+}
+
+/// Returns the width of the gutter on the left of the board that holds the row numbers.
+fn gutter_width(minefield: &utils::Minefield) -> i32 {
+    (minefield.layer_height().saturating_sub(1).to_string().len() + 1) as i32
+}
+
+/// Returns the number of screen columns each cell takes up. Hexagonal boards use two,
+/// so that every odd row can be shifted right by half a cell.
+fn cell_width(minefield: &utils::Minefield) -> i32 {
+    if minefield.topology.neighbourhood == Neighbourhood::Hex {
+        2
+    } else {
+        1
+    }
+}
+
+/// Returns how many screen columns the cells of board row `y` are shifted right.
+/// On a layered board every layer is shifted as if it started at row 0.
+fn row_shift(minefield: &utils::Minefield, y: usize) -> i32 {
+    if minefield.topology.neighbourhood == Neighbourhood::Hex && y % minefield.layer_height() % 2 == 1 {
+        1
+    } else {
+        0
+    }
+}
+
+/// Returns the screen position of the board cell at `pos`, which must be inside the
+/// viewport, as `(row, column)`.
+fn screen_position(minefield: &utils::Minefield, viewport: &Viewport, pos: Pos) -> (i32, i32) {
+    (
+        BOARD_ROW + (pos.y - viewport.y) as i32,
+        gutter_width(minefield) + (pos.x - viewport.x) as i32 * cell_width(minefield) + row_shift(minefield, pos.y),
+    )
+}
+
+/// Maps a screen position to the board cell drawn there, going through the board
+/// layout and the viewport offset.
+///
+/// # Returns
+///
+/// The cell at that position, or `None` if there is no cell there.
+fn cell_at_screen(minefield: &utils::Minefield, viewport: &Viewport, row: i32, column: i32) -> Option<Pos> {
+    let board_row = row - BOARD_ROW;
+    let shift = row_shift(minefield, viewport.y + board_row.max(0) as usize);
+    let board_column = (column - gutter_width(minefield) - shift).div_euclid(cell_width(minefield));
+    viewport.cell_at(board_column, board_row)
+}
+
+/// Draws the coordinate rulers along the top and left of the viewport, so the player
+/// can tell which part of the board is on screen.
+///
+/// Every tenth column is labelled with its number and every fifth is marked with a
+/// `+`. Each visible row is labelled with its number in the gutter, counted from the
+/// top of its layer on a layered board.
+///
+/// # Arguments
+///
+/// * `minefield`: A reference to the game's minefield.
+/// * `viewport`: The part of the board shown on the screen.
+fn draw_rulers(minefield: &utils::Minefield, viewport: &Viewport) {
+    let gutter = gutter_width(minefield);
+    let width = cell_width(minefield) as usize;
+
+    // Build the column ruler, letting labels run into the following columns.
+    let mut ruler = vec![' '; viewport.columns * width];
+    for (i, x) in (viewport.x..viewport.x + viewport.columns).enumerate() {
+        let i = i * width;
+        if x % 10 == 0 {
+            for (j, digit) in x.to_string().chars().enumerate() {
+                if let Some(slot) = ruler.get_mut(i + j) {
+                    *slot = digit;
+                }
+            }
+        } else if x % 5 == 0 && ruler[i] == ' ' {
+            ruler[i] = '+';
+        }
+    }
+    mvaddstr(BOARD_ROW - 1, gutter, &ruler.into_iter().collect::<String>());
+
+    // Label every visible row in the gutter.
+    for row in 0..viewport.rows {
+        mvaddstr(
+            BOARD_ROW + row as i32,
+            0,
+            &format!("{:>width$}", (viewport.y + row) % minefield.layer_height(), width = gutter as usize - 1),
+        );
+    }
+}
+
+/// Draws the end of game statistics below the board and records the game.
+///
+/// # Arguments
+///
+/// * `minefield`: A reference to the game's minefield.
+/// * `stats`: The live statistics of the game.
+/// * `row`: The screen row to draw the statistics at.
+/// * `won`: Whether the player won the game.
+fn draw_summary(minefield: &utils::Minefield, stats: &Stats, row: i32, won: bool) {
+    let mut difficulty = records::difficulty_label(
+        minefield.width,
+        minefield.layer_height(),
+        minefield.mine_count(),
+        minefield.topology,
+    );
+    // Games with extra lives are only compared with games with as many lives.
+    if stats.lives > 1 {
+        difficulty.push_str(&format!(" with {} lives", stats.lives));
+    }
+    let record = records::Record {
+        difficulty,
+        won,
+        seconds: stats.elapsed(),
+        three_bv: minefield.three_bv(),
+        clicks: stats.clicks(),
+        practice: stats.practice,
+        assisted: stats.assisted,
+    };
+
+    // `mvaddstr` is used as `mvprintw` would treat the `%` as a format directive.
+    mvaddstr(
+        row,
+        0,
+        &format!(
+            "Time: {:.2}s  3BV: {}  3BV/s: {:.2}  Efficiency: {:.0}%",
+            record.seconds,
+            record.three_bv,
+            record.three_bv_per_second(),
+            record.efficiency() * 100.0,
+        ),
+    );
+
+    // Only finished games feed the records, and only wins are ever compared.
+    if records::save(&record).is_err() {
+        mvprintw(row + 1, 0, "Could not save the records file.");
+        return;
+    }
+    if record.practice {
+        mvprintw(row + 1, 0, "Practice game, not counted in the records.");
+    } else if record.assisted {
+        mvprintw(row + 1, 0, "Assisted game, not counted in the records.");
+    } else if let (Some(fastest), Some(quickest)) = records::best(&records::load(), &record.difficulty) {
+        mvprintw(
+            row + 1,
+            0,
+            &format!(
+                "{} best: {:.2}s  best 3BV/s: {:.2}",
+                record.difficulty,
+                fastest.seconds,
+                quickest.three_bv_per_second(),
+            ),
+        );
+    }
+}
+
+/// Returns the number of board columns and rows that fit on the screen, leaving
+/// room for the status bar, the rulers, the footer and, on a layered board, the
+/// strip summarising the layers above and below.
+fn screen_space(minefield: &utils::Minefield) -> (usize, usize) {
+    // Hexagonal boards need one more column for the shifted rows.
+    let shifted = (cell_width(minefield) - 1).max(0);
+    let strip = if minefield.topology.layers > 1 { STRIP_WIDTH } else { 0 };
+    let columns = ((getmaxx(stdscr()) - gutter_width(minefield) - shifted - strip) / cell_width(minefield)).max(0) as usize;
+    let rows = (getmaxy(stdscr()) - BOARD_ROW - FOOTER_ROWS).max(0) as usize;
+    (columns, rows)
+}
 
 /// Draws the current state of the game to the screen.
 ///
 /// # Arguments
 ///
 /// * `minefield`: A reference to the game's minefield.
-/// * `revealed`: A `HashSet` containing coordinates of the cells that have been revealed.
-/// * `flagged`: A `HashSet` containing coordinates of the cells that have been flagged as mines.
-/// * `cursor_x`: The x coordinate of the cursor.
-/// * `cursor_y`: The y coordinate of the cursor.
+/// * `cursor`: The position of the cursor, or `None` to hide it.
+/// * `viewport`: The part of the board shown on the screen. Only these cells are drawn.
+/// * `show_mines`: Whether to show every mine, as at the end of a lost game.
 fn draw_game_state(
     minefield: &utils::Minefield,
-    revealed: &HashSet<(usize, usize)>,
-    flagged: &HashSet<(usize, usize)>,
-    cursor_x: usize,
-    cursor_y: usize,
+    cursor: Option<Pos>,
+    viewport: &Viewport,
+    show_mines: bool,
 ) {
-    // Iterate over each row of the game grid.
-    for y in 0..HEIGHT {
-        // Iterate over each column in the current row.
-        for x in 0..WIDTH {
-            // Initialize color_pair to 0, meaning no special color.
-            let mut color_pair: i16 = 0;
-            
-            // Determine the character to display at (x, y).
-            let ch = if cursor_x == x && cursor_y == y {
-                // If the cursor is positioned over this cell.
-                '#'
-            } else if flagged.contains(&(y, x)) {
-                // If this cell is flagged as a mine.
-                color_pair = 10; // Use color pair for flags.
-                'F'
-            } else if revealed.contains(&(y, x)) {
-                // If this cell has been revealed.
-                if minefield.grid.contains(&(x, y)) {
-                    // If the revealed cell is a mine.
-                    color_pair = 1; // Use color pair for mines.
-                    '*'
-                } else {
-                    // If the revealed cell is not a mine.
-                    // Count the number of adjacent mines.
-                    let count: u32 = utils::count_adjacent_mines(&minefield, x, y).into();
-                    color_pair = count as i16 + 1; // Use color pair based on count.
-                    // Display the number of adjacent mines.
-                    std::char::from_digit(count, 10).unwrap_or(' ')
-                }
-            } else {
-                // If the cell has not been revealed yet.
-                '.'
-            };
+    // Iterate over each visible row of the game grid.
+    for y in viewport.y..viewport.y + viewport.rows {
+        // Iterate over each visible column in the current row.
+        for x in viewport.x..viewport.x + viewport.columns {
+            let pos = Pos::new(x, y);
 
-            // Apply the color pair if it is set.
-            if color_pair != 0 {
-                attron(COLOR_PAIR(color_pair));
-            }
-            
-            // Move to the position (x, y) on the screen and add the character.
-            mvaddch(y as i32, x as i32, ch as u32);
-            
-            // Turn off the current color pair if it was set.
-            if color_pair != 0 {
-                attroff(COLOR_PAIR(color_pair));
-            }
+            // Move to the position of the cell inside the viewport and draw it.
+            let (row, column) = screen_position(minefield, viewport, pos);
+            draw_cell(minefield, pos, row, column, cursor == Some(pos), show_mines);
+        }
+    }
+}
+
+/// Draws a single cell of the board at the given screen position.
+///
+/// # Arguments
+///
+/// * `minefield`: A reference to the game's minefield.
+/// * `pos`: The cell to draw.
+/// * `row`, `column`: The screen position to draw it at.
+/// * `is_cursor`: Whether the cursor is over the cell.
+/// * `show_mines`: Whether to show the mine in the cell, as at the end of a lost game.
+fn draw_cell(minefield: &utils::Minefield, pos: Pos, row: i32, column: i32, is_cursor: bool, show_mines: bool) {
+    // Initialize color_pair to 0, meaning no special color.
+    let mut color_pair: i16 = 0;
+
+    // Determine the character to display for the cell.
+    let ch = if is_cursor {
+        // If the cursor is positioned over this cell.
+        '#'
+    } else if minefield.is_flagged(pos) {
+        // If this cell is flagged as a mine.
+        color_pair = 10; // Use color pair for flags.
+        'F'
+    } else if minefield.is_questioned(pos) && !minefield.is_revealed(pos) {
+        // If this cell is marked with a question mark.
+        '?'
+    } else if minefield.is_revealed(pos) || (show_mines && minefield.is_mine(pos)) {
+        // If this cell has been revealed.
+        if minefield.is_mine(pos) {
+            // If the revealed cell is a mine.
+            color_pair = 1; // Use color pair for mines.
+            '*'
+        } else {
+            // If the revealed cell is not a mine.
+            // Look up the number of adjacent mines.
+            let count: u32 = minefield.adjacent_mines(pos).into();
+            // Use color pair based on count, sharing the last one above 8.
+            color_pair = count.min(8) as i16 + 1;
+            // Display the number of adjacent mines, using the letters a to q for
+            // the counts of 10 to 26 only found on layered boards.
+            std::char::from_digit(count, 36).unwrap_or(' ')
+        }
+    } else {
+        // If the cell has not been revealed yet.
+        '.'
+    };
+
+    // Apply the color pair if it is set.
+    if color_pair != 0 {
+        attron(COLOR_PAIR(color_pair));
+    }
+
+    mvaddch(row, column, ch as u32);
+
+    // Turn off the current color pair if it was set.
+    if color_pair != 0 {
+        attroff(COLOR_PAIR(color_pair));
+    }
+}
+
+/// Returns how many cells away from the cursor the windows of the layer strip reach.
+/// Knight neighbourhoods reach two cells away, the others only one.
+fn strip_reach(minefield: &utils::Minefield) -> isize {
+    if minefield.topology.neighbourhood == Neighbourhood::Knight {
+        2
+    } else {
+        1
+    }
+}
+
+/// Returns the screen row below the board, and below the layer strip of a layered
+/// board, where messages are shown.
+fn footer_row(minefield: &utils::Minefield, viewport: &Viewport) -> i32 {
+    let strip_rows = if minefield.topology.layers > 1 { 7 + 4 * strip_reach(minefield) as i32 } else { 0 };
+    BOARD_ROW + (viewport.rows as i32).max(strip_rows)
+}
+
+/// Draws the strip to the right of a layered board that summarises the layers
+/// directly above and below the one shown.
+///
+/// For each of those layers, the cells around the spot under the cursor are drawn
+/// as a small window, so the neighbours of the cursor in other layers can be seen
+/// without switching layers. Cells that are not neighbours of the cursor are left
+/// blank.
+///
+/// # Arguments
+///
+/// * `minefield`: A reference to the game's minefield.
+/// * `cursor`: The position of the cursor.
+/// * `viewport`: The part of the board shown on the screen.
+/// * `show_mines`: Whether to show every mine, as at the end of a lost game.
+fn draw_layer_strip(minefield: &utils::Minefield, cursor: Pos, viewport: &Viewport, show_mines: bool) {
+    let layers = minefield.topology.layers;
+    if layers < 2 {
+        return;
+    }
+    let layer_height = minefield.layer_height();
+    let layer = cursor.y / layer_height;
+    let column = gutter_width(minefield) + viewport.columns as i32 * cell_width(minefield) + 2;
+
+    let reach = strip_reach(minefield);
+    let topology = minefield.topology;
+
+    let mut row = BOARD_ROW;
+    mvaddstr(row, column, &format!("Layer {}/{}", layer + 1, layers));
+    for (label, other) in [("Above", layer.checked_sub(1)), ("Below", Some(layer + 1).filter(|&l| l < layers))] {
+        // Leave a blank row before each window, and always keep the room for it so
+        // the strip does not jump around when the cursor reaches the top or bottom.
+        row += 2;
+        let window = row + 1;
+        row += 1 + 2 * reach as i32;
+        let Some(other) = other else {
+            mvaddstr(window - 1, column, &format!("{}: none", label));
+            continue;
+        };
+        mvaddstr(window - 1, column, &format!("{}: {}", label, other + 1));
+
+        // Place every neighbour of the cursor in that layer in the window.
+        for neighbour in minefield.neighbours(cursor).filter(|n| n.y / layer_height == other) {
+            let dx = topology.offset(cursor.x, neighbour.x, minefield.width);
+            let dy = topology.offset(cursor.y % layer_height, neighbour.y % layer_height, layer_height);
+            draw_cell(
+                minefield,
+                neighbour,
+                window + (reach + dy) as i32,
+                column + (reach + dx) as i32 * 2,
+                false,
+                show_mines,
+            );
+        }
+    }
+}
+
+/// Tracks which mouse buttons are held, so pressing left and right together can be
+/// recognised as a chord.
+struct MouseButtons {
+    /// Whether the left button is held.
+    left: bool,
+    /// Whether the right button is held.
+    right: bool,
+    /// Whether the buttons currently held have already chorded.
+    chorded: bool,
+}
+
+/// Turns a key press into an action on the cell under the cursor.
+///
+/// # Arguments
+///
+/// * `minefield`: A reference to the game's minefield.
+/// * `input`: The key that was pressed.
+/// * `cursor`: The position of the cursor.
+///
+/// # Returns
+///
+/// The action for the key, or `None` if the key does not act on the board.
+fn key_action(minefield: &utils::Minefield, input: i32, cursor: Pos) -> Option<Action> {
+    match input {
+        // Enter on a revealed number chords on it, otherwise it reveals the cell.
+        10 if minefield.is_revealed(cursor) => Some(Action::Chord(cursor)),
+        10 => Some(Action::Reveal(cursor)),
+        // c chords on the number under the cursor.
+        99 => Some(Action::Chord(cursor)),
+        // Space cycles the mark under the cursor: flag, question mark, none.
+        32 => Some(Action::Mark(cursor)),
+        _ => None,
+    }
+}
+
+/// Turns a mouse event into an action on the clicked cell.
+///
+/// Left click reveals, right click cycles the mark, and middle click or pressing left and right
+/// together chords. A left click on a revealed number also chords, like Enter.
+///
+/// # Arguments
+///
+/// * `minefield`: A reference to the game's minefield.
+/// * `bstate`: The button state of the mouse event.
+/// * `cell`: The cell under the mouse, or `None` if the mouse is off the board.
+/// * `buttons`: The buttons held before this event, updated in place.
+///
+/// # Returns
+///
+/// The action for the event, or `None` if it does not act on the board.
+fn mouse_action(
+    minefield: &utils::Minefield,
+    bstate: mmask_t,
+    cell: Option<Pos>,
+    buttons: &mut MouseButtons,
+) -> Option<Action> {
+    let has = |mask: i32| bstate & mask as mmask_t != 0;
+
+    if has(BUTTON1_PRESSED) {
+        buttons.left = true;
+    }
+    if has(BUTTON3_PRESSED) {
+        buttons.right = true;
+        // A right press on its own marks straight away, as in the classic game.
+        if !buttons.left {
+            return cell.map(Action::Mark);
         }
     }
+
+    if has(BUTTON1_RELEASED) || has(BUTTON3_RELEASED) {
+        let both = buttons.left && buttons.right;
+        let left_only = has(BUTTON1_RELEASED) && buttons.left && !buttons.right;
+        if has(BUTTON1_RELEASED) {
+            buttons.left = false;
+        }
+        if has(BUTTON3_RELEASED) {
+            buttons.right = false;
+        }
+
+        // Releasing either button of a left and right press chords, and the release
+        // of the other button is then ignored.
+        if both {
+            buttons.chorded = true;
+            return cell.map(Action::Chord);
+        }
+        let chorded = buttons.chorded;
+        if !buttons.left && !buttons.right {
+            buttons.chorded = false;
+        }
+        let reveal = left_only && !chorded;
+        return cell.filter(|_| reveal).and_then(|cell| key_action(minefield, 10, cell));
+    }
+
+    // Terminals that only report whole clicks, and the middle button, are handled here.
+    let cell = cell?;
+    if has(BUTTON1_CLICKED) {
+        key_action(minefield, 10, cell)
+    } else if has(BUTTON3_CLICKED) {
+        Some(Action::Mark(cell))
+    } else if has(BUTTON2_CLICKED) || has(BUTTON2_RELEASED) {
+        Some(Action::Chord(cell))
+    } else {
+        None
+    }
+}
+
+/// Saves the minefield to a new board file in the current directory.
+///
+/// # Arguments
+/// * `minefield`: A reference to the game's minefield.
+/// * `with_state`: Whether to include the revealed cells and the marks.
+///
+/// # Returns
+/// A message saying where the board was saved, or why it could not be.
+fn export_board(minefield: &utils::Minefield, with_state: bool) -> String {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let path = PathBuf::from(format!("minesweeper-{}.txt", seconds));
+    match board_file::save(minefield, with_state, &path) {
+        Ok(()) => format!("Saved the board to {}", path.display()),
+        Err(e) => e,
+    }
 }
 
 /// Play a game of Minesweeper
 ///
 /// # Arguments
 /// * `width`: The width of the minefield.
-/// * `height`: The height of the minefield.
+/// * `height`: The height of the minefield, or of each layer of a layered one.
+/// * `mines`: The number of mines in the minefield.
+/// * `topology`: Which cells are neighbours, whether the edges wrap around and how
+///   many layers there are.
+/// * `options`: The optional rules, such as extra lives.
+pub fn minesweeper(width: usize, height: usize, mines: usize, topology: Topology, options: Options) {
+    play(utils::generate_minefield(width, height * topology.layers, mines, topology), options);
+}
+
+/// Play a hot-seat game of Minesweeper Flags for two players, where finding a mine
+/// scores a point.
+///
+/// # Arguments
+/// * `width`: The width of the minefield.
+/// * `height`: The height of the minefield, or of each layer of a layered one.
 /// * `mines`: The number of mines in the minefield.
-pub fn minesweeper(width: usize, height: usize, mines: usize) {
-    // Create the minefield.
-    let minefield = utils::generate_minefield(width, height, mines);
-    // The set of cells that have been revealed.
-    let mut revealed = HashSet::<(usize, usize)>::with_capacity((width * height) as usize);
-    // The set of cells that have been flagged as mines.
-    let mut flagged = HashSet::<(usize, usize)>::with_capacity(mines);
+/// * `topology`: Which cells are neighbours, whether the edges wrap around and how
+///   many layers there are.
+pub fn minesweeper_flags(width: usize, height: usize, mines: usize, topology: Topology) {
+    flags::play(utils::generate_minefield(width, height * topology.layers, mines, topology));
+}
+
+/// Runs the interactive tutorial, which teaches the classic patterns on hand-made
+/// boards and checks every move before making it.
+pub fn minesweeper_tutorial() -> Result<(), Box<dyn std::error::Error>> {
+    tutorial::play()?;
+    Ok(())
+}
+
+/// Play a game of Minesweeper on a board loaded from a board file, restoring any
+/// revealed cells and marks saved with it.
+///
+/// # Arguments
+/// * `path`: The path of the board file.
+/// * `options`: The optional rules, such as extra lives.
+pub fn minesweeper_from_file(path: &Path, options: Options) -> Result<(), Box<dyn std::error::Error>> {
+    play(board_file::load(path)?, options);
+    Ok(())
+}
 
+/// Lets the autoplay bot play seeded games on each board size and prints a table of
+/// the win rate, 3BV/s and guesses for each, without starting ncurses.
+///
+/// # Arguments
+/// * `sizes`: The width, height and mines of each board to play.
+/// * `topology`: Which cells are neighbours, whether the edges wrap around and how
+///   many layers there are.
+/// * `strategy`: How the bot chooses its moves.
+/// * `games`: The number of games to play on each board size.
+/// * `seed`: The seed of the first game, each further game using the next one.
+pub fn benchmark(sizes: &[(usize, usize, usize)], topology: Topology, strategy: Strategy, games: u32, seed: u64) {
+    println!("Strategy: {}  Games: {}  Seed: {}", strategy.name(), games, seed);
+    println!("{:<32} {:>6} {:>9} {:>10} {:>13}", "Difficulty", "Wins", "Win rate", "3BV/s", "Guesses/game");
+    for &size in sizes {
+        let summary = bot::benchmark(size, topology, strategy, games, seed);
+        println!(
+            "{:<32} {:>6} {:>8.1}% {:>10.1} {:>13.2}",
+            summary.difficulty,
+            summary.wins,
+            summary.win_rate(),
+            summary.three_bv_per_second(),
+            summary.guesses_per_game(),
+        );
+    }
+}
+
+/// Starts ncurses for a game of Minesweeper: no echo, arrow keys, mouse events and
+/// the colours of the numbers, mines and flags.
+fn start_screen() {
     // Initialize the ncurses library.
     initscr(); // Initialize the ncurses library.
     noecho(); // Do not echo the keys pressed.
     curs_set(CURSOR_VISIBILITY::CURSOR_INVISIBLE); // Make the cursor invisible.
     keypad(stdscr(), true); // Enable reading of special keys such as arrow keys.
     timeout(100); // Set the timeout to 100ms.
+    // Report mouse button presses and releases straight away, so left and right
+    // pressed together can be told apart from two separate clicks. Plain movement is
+    // not reported, so moving the mouse never drags the cursor away.
+    mousemask(ALL_MOUSE_EVENTS as mmask_t, None);
+    mouseinterval(0);
 
     // Initialize colors if supported.
     if has_colors() {
@@ -148,25 +753,362 @@ pub fn minesweeper(width: usize, height: usize, mines: usize) {
             COLOR_RED,
             COLOR_WHITE,
         ); // Color pair for flags.
+        init_pair(
+            11,
+            COLOR_WHITE,
+            COLOR_BLUE,
+        ); // Color pair for the mines claimed by the first player of Minesweeper Flags.
+        init_pair(
+            12,
+            COLOR_BLACK,
+            COLOR_YELLOW,
+        ); // Color pair for the mines claimed by the second player of Minesweeper Flags.
+    }
+}
+
+/// Draws the board at the end of a game, showing how every mine and flag turned out.
+///
+/// * `@` - the mine that ended the game.
+/// * `x` - a mine that cost a life.
+/// * `F` - a correctly flagged mine.
+/// * `X` - a flag on a safe cell.
+/// * `*` - a mine that was neither flagged nor set off, i.e. missed, or just left
+///   unflagged after a win.
+///
+/// Every other cell is drawn as usual.
+///
+/// # Arguments
+///
+/// * `minefield`: A reference to the game's minefield.
+/// * `viewport`: The part of the board shown on the screen.
+/// * `spent`: The mines that only cost a life.
+fn draw_post_mortem_board(minefield: &utils::Minefield, viewport: &Viewport, spent: &[Pos]) {
+    for y in viewport.y..viewport.y + viewport.rows {
+        for x in viewport.x..viewport.x + viewport.columns {
+            let pos = Pos::new(x, y);
+            let (row, column) = screen_position(minefield, viewport, pos);
+            let (ch, color_pair) = match (minefield.is_mine(pos), minefield.is_flagged(pos)) {
+                (true, _) if minefield.is_revealed(pos) && spent.contains(&pos) => ('x', 1),
+                (true, _) if minefield.is_revealed(pos) => ('@', 9),
+                (true, true) => ('F', 10),
+                (false, true) => ('X', 8),
+                (true, false) => ('*', 1),
+                (false, false) => {
+                    draw_cell(minefield, pos, row, column, false, false);
+                    continue;
+                }
+            };
+            attron(COLOR_PAIR(color_pair));
+            mvaddch(row, column, ch as u32);
+            attroff(COLOR_PAIR(color_pair));
+        }
+    }
+}
+
+/// Shows the end of game screen: the board with every mine and flag accounted for,
+/// the counts of correct and wrong flags and missed mines, and the statistics of the
+/// game, then waits for a key.
+///
+/// # Arguments
+///
+/// * `minefield`: A reference to the game's minefield.
+/// * `stats`: The statistics of the game.
+/// * `viewport`: The part of the board shown on the screen.
+/// * `cursor`: The position of the cursor, which the viewport keeps following.
+/// * `spent`: The mines that only cost a life.
+/// * `won`: Whether the player won the game.
+fn post_mortem(
+    minefield: &utils::Minefield,
+    stats: &Stats,
+    viewport: &mut Viewport,
+    cursor: Pos,
+    spent: &[Pos],
+    won: bool,
+) {
+    let (correct, wrong, missed) = minefield.flag_tally();
+
+    erase();
+    viewport.follow((minefield.width, minefield.layer_height()), screen_space(minefield), cursor);
+    draw_status_bar(stats, minefield, cursor);
+    draw_rulers(minefield, viewport);
+    draw_post_mortem_board(minefield, viewport, spent);
+    draw_layer_strip(minefield, cursor, viewport, true);
+
+    // Judge every decision of the game, replayed from the undo history. The timer
+    // was stopped when the game ended, so the analysis does not add to the time.
+    let report = luck::analyse(minefield);
+
+    let row = footer_row(minefield, viewport);
+    let verdict = match &report {
+        Some(report) => format!("Luck needed: {:.0}%  Skill: {:.0}%", report.luck(), report.skill()),
+        None => format!("Analysis skipped: the board is larger than {} cells.", luck::MAX_CELLS),
+    };
+    mvaddstr(row, 0, &format!("{}  {}", if won { "You Won!" } else { "Game Over!" }, verdict));
+    // After a win the mines left over were not missed, only never flagged.
+    let missed_label = if won { "unflagged" } else { "missed" };
+    mvaddstr(
+        row + 1,
+        0,
+        &format!("@ exploded  x cost a life  F flagged  X wrong flag  * {}", missed_label),
+    );
+    let mut counts = format!("Flags: {} correct, {} wrong  Mines {}: {}", correct, wrong, missed_label, missed);
+    if stats.lives > 1 {
+        counts.push_str(&format!("  Lives used: {}/{}", stats.lives - stats.lives_left(minefield), stats.lives));
+    }
+    mvaddstr(row + 2, 0, &counts);
+    draw_summary(minefield, stats, row + 3, won);
+    if report.is_some() {
+        mvaddstr(row + 5, 0, "Press a for the decision breakdown, any other key to quit.");
+    } else {
+        mvaddstr(row + 5, 0, "Press any key to quit.");
+    }
+    refresh();
+
+    // Wait for a key without the usual timeout, so the summary can be read.
+    timeout(-1);
+    if let (Some(report), true) = (&report, getch() == 'a' as i32) {
+        show_decisions(minefield, report);
+    }
+    timeout(100);
+}
+
+/// Describes a decision for the breakdown, such as `Reveal (3, 4)  guess, 75% safe`.
+fn describe_decision(minefield: &utils::Minefield, decision: &luck::Decision) -> String {
+    let (verb, pos) = match decision.action {
+        Action::Reveal(pos) => ("Reveal", pos),
+        Action::Chord(pos) => ("Chord", pos),
+        Action::Mark(pos) => ("Mark", pos),
+    };
+    let mut place = format!("({}, {})", pos.x, pos.y % minefield.layer_height());
+    if minefield.topology.layers > 1 {
+        place.push_str(&format!(" layer {}", pos.y / minefield.layer_height() + 1));
+    }
+    let verdict = match decision.verdict {
+        luck::Verdict::Forced => "forced".to_string(),
+        luck::Verdict::Guess => format!(
+            "guess, {:.0}% safe (best {:.0}%)",
+            decision.survival * 100.0,
+            decision.best_survival * 100.0
+        ),
+        luck::Verdict::Mistake if decision.survival <= 0.0 => "mistake, a proven mine".to_string(),
+        luck::Verdict::Mistake => format!("mistake, {:.0}% safe with a safe cell left", decision.survival * 100.0),
+    };
+    let outcome = if decision.survived { "" } else { "  BOOM" };
+    format!("{:<6} {:<20} {}{}", verb, place, verdict, outcome)
+}
+
+/// Shows every decision of the game with its verdict, in a list that scrolls with the
+/// arrow keys and Page Up and Page Down, until q is pressed.
+///
+/// # Arguments
+///
+/// * `minefield`: A reference to the game's minefield.
+/// * `report`: The analysis of the game's decisions.
+fn show_decisions(minefield: &utils::Minefield, report: &luck::Report) {
+    // The numbered lines are built once, as the list does not change while it scrolls.
+    let lines: Vec<String> = report
+        .decisions
+        .iter()
+        .enumerate()
+        .map(|(i, decision)| format!("{:>4}. {}", i + 1, describe_decision(minefield, decision)))
+        .collect();
+    let mut top = 0;
+
+    loop {
+        let page = (LINES() - 3).max(1) as usize;
+        top = top.min(lines.len().saturating_sub(page));
+
+        erase();
+        mvaddstr(
+            0,
+            0,
+            &format!(
+                "Decisions: {}  Forced: {}  Guesses: {}  Mistakes: {}  Luck needed: {:.0}%  Skill: {:.0}%",
+                lines.len(),
+                report.count(luck::Verdict::Forced),
+                report.count(luck::Verdict::Guess),
+                report.count(luck::Verdict::Mistake),
+                report.luck(),
+                report.skill(),
+            ),
+        );
+        for (i, line) in lines.iter().skip(top).take(page).enumerate() {
+            mvaddstr(i as i32 + 2, 0, line);
+        }
+        mvaddstr(LINES() - 1, 0, "Arrows and Page Up/Down scroll, q quits.");
+        refresh();
+
+        match getch() {
+            KEY_UP => top = top.saturating_sub(1),
+            KEY_DOWN => top += 1,
+            KEY_PPAGE => top = top.saturating_sub(page),
+            KEY_NPAGE => top += page,
+            input if input == 'q' as i32 => break,
+            _ => (),
+        }
+    }
+}
+
+/// Stops reporting mouse events and cleans up the ncurses library.
+fn end_screen() {
+    mousemask(0, None);
+    endwin();
+}
+
+/// Reads the action for a key press or mouse event, if it is one, moving the cursor
+/// to a clicked cell.
+///
+/// # Arguments
+///
+/// * `minefield`: A reference to the game's minefield.
+/// * `input`: The key read by `getch`, or `KEY_MOUSE` for a mouse event.
+/// * `viewport`: The part of the board shown on the screen.
+/// * `cursor`: The position of the cursor, updated by clicks.
+/// * `buttons`: The mouse buttons held, updated by mouse events.
+fn read_action(
+    minefield: &utils::Minefield,
+    input: i32,
+    viewport: &Viewport,
+    cursor: &mut Pos,
+    buttons: &mut MouseButtons,
+) -> Option<Action> {
+    if input != KEY_MOUSE {
+        return key_action(minefield, input, *cursor);
+    }
+    let mut event = MEVENT { id: 0, x: 0, y: 0, z: 0, bstate: 0 };
+    if getmouse(&mut event) != OK {
+        return None;
+    }
+    // Map the click through the board layout and the viewport offset.
+    let cell = cell_at_screen(minefield, viewport, event.y, event.x);
+    // Pressing or clicking a button on a cell also moves the cursor there. Terminals
+    // that report movement anyway send it without a button, so it is ignored.
+    if let Some(cell) = cell.filter(|_| event.bstate & ALL_MOUSE_EVENTS as mmask_t != 0) {
+        *cursor = cell;
+    }
+    mouse_action(minefield, event.bstate, cell, buttons)
+}
+
+/// Moves the cursor for the arrow, paging and layer keys, keeping it inside its layer.
+fn move_cursor(minefield: &utils::Minefield, input: i32, viewport: &Viewport, cursor: &mut Pos) {
+    let (width, height) = (minefield.width, minefield.height);
+    let layer_height = minefield.layer_height();
+    let top = cursor.y - cursor.y % layer_height;
+    let bottom = top + layer_height - 1;
+    match input {
+        KEY_UP => cursor.y = cursor.y.saturating_sub(1).max(top),
+        KEY_DOWN => cursor.y = min(cursor.y + 1, bottom),
+        KEY_LEFT => cursor.x = cursor.x.saturating_sub(1),
+        KEY_RIGHT => cursor.x = min(cursor.x + 1, width - 1),
+        // Page Up and Page Down jump the cursor a whole viewport up or down,
+        // Home and End jump it a whole viewport left or right.
+        KEY_PPAGE => cursor.y = cursor.y.saturating_sub(viewport.rows.max(1)).max(top),
+        KEY_NPAGE => cursor.y = min(cursor.y + viewport.rows.max(1), bottom),
+        KEY_HOME => cursor.x = cursor.x.saturating_sub(viewport.columns.max(1)),
+        KEY_END => cursor.x = min(cursor.x + viewport.columns.max(1), width - 1),
+        // < and > move the cursor to the same spot one layer up or down.
+        60 if cursor.y >= layer_height => cursor.y -= layer_height,
+        62 if cursor.y + layer_height < height => cursor.y += layer_height,
+        _ => (),
     }
+}
+
+/// Makes the moves of the assists that are switched on, until neither finds any more.
+///
+/// Auto-flag flags the cells that single numbers prove to be mines, and auto-open
+/// reveals the cells they prove safe. Both only go by the revealed numbers, never
+/// by the player's flags, so a wrong flag cannot make an assist set off a mine.
+/// The moves go through `Minefield::apply_assist`, so they can be undone one at a
+/// time and the analysis of the game knows they were not the player's.
+///
+/// # Returns
+///
+/// `true` if auto-open revealed a mine, `false` otherwise.
+fn run_assists(minefield: &mut utils::Minefield, stats: &mut Stats) -> bool {
+    loop {
+        let mut changed = false;
+        let deductions = solver::trivial(minefield);
+
+        if stats.auto_flag {
+            for deduction in deductions.iter().filter(|d| d.mine) {
+                if minefield.is_flagged(deduction.pos) {
+                    continue;
+                }
+                // A question mark has to be cycled off before the flag goes on, which
+                // takes at most two cycles of the mark.
+                for _ in 0..2 {
+                    if !minefield.is_flagged(deduction.pos) {
+                        minefield.apply_assist(Action::Mark(deduction.pos));
+                    }
+                }
+                changed |= minefield.is_flagged(deduction.pos);
+            }
+        }
+
+        if stats.auto_open {
+            for deduction in deductions.iter().filter(|d| !d.mine) {
+                // A safe cell the player flagged by mistake is left for them to fix,
+                // and an earlier reveal may already have opened it.
+                if minefield.is_flagged(deduction.pos) || minefield.is_revealed(deduction.pos) {
+                    continue;
+                }
+                changed = true;
+                if minefield.apply_assist(Action::Reveal(deduction.pos)) {
+                    stats.assisted = true;
+                    return true;
+                }
+            }
+        }
+
+        if !changed {
+            return false;
+        }
+        stats.assisted = true;
+    }
+}
+
+/// Plays a game of Minesweeper on the given minefield until it is won, lost or quit.
+///
+/// # Arguments
+/// * `minefield`: The minefield to play on.
+/// * `options`: The optional rules, such as extra lives.
+fn play(mut minefield: utils::Minefield, options: Options) {
+    let width = minefield.width;
+    // Layered boards are played one layer at a time.
+    let layer_height = minefield.layer_height();
+
+    start_screen();
 
     // Initialize the position of the cursor.
-    let mut cursor_x = 0;
-    let mut cursor_y = 0;
+    let mut cursor = Pos::new(0, 0);
+    // The part of the board shown on the screen, which follows the cursor.
+    let mut viewport = Viewport::new();
+    // The statistics of the game so far.
+    let mut stats = Stats::new(options);
+    // The mouse buttons currently held.
+    let mut buttons = MouseButtons { left: false, right: false, chorded: false };
+    // A message shown below the board, such as where a board was saved.
+    let mut message = String::new();
+    // The mines set off that only cost a life, told apart from the one that ends the game.
+    let mut spent: Vec<Pos> = Vec::new();
 
     // The loop that controls the game.
     loop {
-        // Clear the screen.
-        clear();
+        // Clear the screen without forcing a full repaint.
+        erase();
 
-        // Draw the game state.
+        // Scroll the viewport to follow the cursor, then draw the status bar and the game state.
+        viewport.follow((width, layer_height), screen_space(&minefield), cursor);
+        draw_status_bar(&stats, &minefield, cursor);
+        draw_rulers(&minefield, &viewport);
         draw_game_state(
             &minefield,
-            &revealed,
-            &flagged,
-            cursor_x,
-            cursor_y,
+            Some(cursor),
+            &viewport,
+            false,
         );
+        draw_layer_strip(&minefield, cursor, &viewport, false);
+        mvaddstr(footer_row(&minefield, &viewport), 0, &message);
 
         // Refresh the screen.
         refresh();
@@ -179,79 +1121,222 @@ pub fn minesweeper(width: usize, height: usize, mines: usize) {
             break;
         }
 
+        // Turn the input into an action on the board, if it is one.
+        let action = read_action(&minefield, input, &viewport, &mut cursor, &mut buttons);
+
+        // Apply the action and record it, so keyboard and mouse play end up in the
+        // same action log.
+        let mut detonated = false;
+        // Whether an action was applied or redone, for the assists to follow up on.
+        let mut moved = false;
+        if let Some(action) = action {
+            stats.record(action);
+            detonated = minefield.apply(action);
+            moved = true;
+        }
+
         // Move the cursor based on the input.
+        move_cursor(&minefield, input, &viewport, &mut cursor);
         match input {
-            KEY_UP => cursor_y = cursor_y.saturating_sub(1),
-            KEY_DOWN => cursor_y = min(cursor_y + 1, height - 1),
-            KEY_LEFT => cursor_x = cursor_x.saturating_sub(1),
-            KEY_RIGHT => cursor_x = min(cursor_x + 1, width - 1),
-            10 => {
-                if !flagged.contains(&(cursor_y, cursor_x)) && !revealed.contains(&(cursor_y, cursor_x)) {
-                    // If the user pressed Enter, reveal the cell at the current position.
-                    utils::reveal_adjacent_zeros(
-                        &minefield,
-                        &mut revealed,
-                        &flagged,
-                        cursor_x,
-                        cursor_y,
-                    );
-
-                    // Check if the user has pressed a mine.
-                    if minefield.grid.contains(&(cursor_x, cursor_y)) {
-                        // If the user has pressed a mine, end the game.
-                        let _ = mvprintw(
-                            height as i32 + 1,
-                            0,
-                            "Game Over!",
-                        );
-                        refresh();
-                        for y in 0..height {
-                            for x in 0..width {
-                                if minefield.grid.contains(&(x, y)) {
-                                    attron(COLOR_PAIR(1));
-                                    mvaddch(y as i32, x as i32, '*' as u32);
-                                    attroff(COLOR_PAIR(1));
-                                } else if revealed.contains(&(y, x)) {
-                                    let count = utils::count_adjacent_mines(&minefield, x, y);
-                                    let ch = std::char::from_digit(count as u32, 10).unwrap_or(' ');
-                                    attron(COLOR_PAIR(count as i16 + 1));
-                                    mvaddch(y as i32, x as i32, ch as u32);
-                                    attroff(COLOR_PAIR(count as i16 + 1));
-                                }
-                            }
-                        }
-                        refresh();
-                        napms(2000);
-                        break;
-                    }
+            // u undoes the last action, r redoes the last undone action.
+            117 => {
+                // Undoing a mine hit, even one that only cost a life, turns the game
+                // into a practice game.
+                let lives_left = stats.lives_left(&minefield);
+                minefield.undo();
+                if stats.lives_left(&minefield) > lives_left {
+                    stats.practice = true;
+                    spent.retain(|&p| minefield.is_revealed(p));
                 }
             }
-            32 => {
-                // If the user pressed the Space key, toggle the flag on the cell at the current position.
-                if !revealed.contains(&(cursor_y, cursor_x)) {
-                    if flagged.contains(&(cursor_y, cursor_x)) {
-                        flagged.retain(|&(y, x)| !(y == cursor_y && x == cursor_x));
-                    } else {
-                        flagged.insert((cursor_y, cursor_x));
-                    }
+            114 => {
+                if let Some(redone) = minefield.redo() {
+                    detonated = redone;
+                    moved = true;
                 }
             }
+            // f and o switch the auto-flag and auto-open assists on and off.
+            102 => stats.auto_flag = !stats.auto_flag,
+            111 => stats.auto_open = !stats.auto_open,
+            // s saves the board with the player's progress, S saves only the mines.
+            115 => message = export_board(&minefield, true),
+            83 => message = export_board(&minefield, false),
             _ => (),
         }
 
-        // Check if the user has won the game.
-        let won = (0..height)
-            .all(|y| (0..width).all(|x| minefield.grid.contains(&(x, y)) || revealed.contains(&(y, x))));
+        // Let the assists follow up on the move. They only run after an action was
+        // applied or redone, never after an undo or on the idle ticks of the input
+        // timeout, or they would redo what was just undone.
+        if moved && !detonated {
+            detonated = run_assists(&mut minefield, &mut stats);
+        }
 
-        if won {
-            // If the user has won, end the game.
-            let _ = mvprintw(height as i32 + 1, 0, "You Won!");
+        // Check if the user has revealed a mine.
+        if detonated && stats.lives_left(&minefield) > 0 {
+            // A mine that only costs a life stays on the board, and play goes on.
+            message = format!("Boom! You lost a life, {} left.", stats.lives_left(&minefield));
+            spent = minefield.exploded_mines().collect();
+        } else if detonated {
+            // If the user has revealed a mine, stop the clock, show every mine and
+            // offer to undo it.
+            stats.stop();
+            draw_status_bar(&stats, &minefield, cursor);
+            draw_game_state(&minefield, None, &viewport, true);
+            draw_layer_strip(&minefield, cursor, &viewport, true);
+            mv(footer_row(&minefield, &viewport), 0);
+            clrtoeol();
+            let _ = mvprintw(
+                footer_row(&minefield, &viewport),
+                0,
+                "Game Over! Press u to undo and keep practising, or any other key to quit.",
+            );
             refresh();
-            napms(2000);
+
+            // Wait for the answer without the usual timeout.
+            timeout(-1);
+            let answer = getch();
+            timeout(100);
+            if answer == 'u' as i32 {
+                // Undoing a mine hit turns the game into a practice game.
+                minefield.undo();
+                stats.practice = true;
+                stats.resume();
+                continue;
+            }
+
+            post_mortem(&minefield, &stats, &mut viewport, cursor, &spent, false);
+            break;
+        }
+
+        // Check if the user has won the game.
+        if minefield.is_won() {
+            stats.stop();
+            post_mortem(&minefield, &stats, &mut viewport, cursor, &spent, true);
             break;
         }
     }
 
-    // Clean up the ncurses library.
-    endwin();
+    // Stop reporting mouse events and clean up the ncurses library.
+    end_screen();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A board with a mine in the corner, the 1 at (1, 1) revealed.
+    fn board() -> utils::Minefield {
+        board_file::from_text("minesweeper 3 3 1\n*..\n.1.\n...\n").unwrap()
+    }
+
+    /// Returns the mouse buttons with none of them held.
+    fn released() -> MouseButtons {
+        MouseButtons { left: false, right: false, chorded: false }
+    }
+
+    /// Feeds a series of mouse events on `cell` to `mouse_action`, one after the other.
+    fn actions(events: &[i32], cell: Option<Pos>) -> Vec<Option<Action>> {
+        let (minefield, mut buttons) = (board(), released());
+        events.iter().map(|&event| mouse_action(&minefield, event as mmask_t, cell, &mut buttons)).collect()
+    }
+
+    #[test]
+    fn a_left_click_reveals_on_release_and_chords_on_a_number() {
+        let hidden = Pos::new(2, 2);
+        assert_eq!(actions(&[BUTTON1_PRESSED, BUTTON1_RELEASED], Some(hidden)), [None, Some(Action::Reveal(hidden))]);
+        let number = Pos::new(1, 1);
+        assert_eq!(actions(&[BUTTON1_PRESSED, BUTTON1_RELEASED], Some(number)), [None, Some(Action::Chord(number))]);
+    }
+
+    #[test]
+    fn a_right_press_marks_straight_away() {
+        let cell = Pos::new(0, 0);
+        assert_eq!(actions(&[BUTTON3_PRESSED, BUTTON3_RELEASED], Some(cell)), [Some(Action::Mark(cell)), None]);
+    }
+
+    #[test]
+    fn left_and_right_together_chord_once() {
+        let number = Pos::new(1, 1);
+        let events = [BUTTON1_PRESSED, BUTTON3_PRESSED, BUTTON3_RELEASED, BUTTON1_RELEASED];
+        assert_eq!(actions(&events, Some(number)), [None, None, Some(Action::Chord(number)), None]);
+    }
+
+    #[test]
+    fn whole_clicks_and_the_middle_button_are_understood() {
+        let cell = Pos::new(2, 0);
+        assert_eq!(actions(&[BUTTON1_CLICKED], Some(cell)), [Some(Action::Reveal(cell))]);
+        assert_eq!(actions(&[BUTTON3_CLICKED], Some(cell)), [Some(Action::Mark(cell))]);
+        assert_eq!(actions(&[BUTTON2_CLICKED], Some(cell)), [Some(Action::Chord(cell))]);
+    }
+
+    #[test]
+    fn movement_and_clicks_off_the_board_do_nothing() {
+        assert_eq!(actions(&[REPORT_MOUSE_POSITION], Some(Pos::new(2, 2))), [None]);
+        assert_eq!(actions(&[BUTTON1_PRESSED, BUTTON1_RELEASED, BUTTON3_CLICKED], None), [None, None, None]);
+    }
+
+    /// A strip where the 1 proves the mine on its left and the 0 proves the cell on its
+    /// right safe, while nothing is known yet about the mine at the far end.
+    const STRIP: &str = "minesweeper 6 1 2\n*10..*\n";
+
+    /// Runs the assists that are switched on over `text` until they stop.
+    fn assisted(text: &str, auto_flag: bool, auto_open: bool) -> (utils::Minefield, Stats, bool) {
+        let mut minefield = board_file::from_text(text).unwrap();
+        let mut stats = Stats::new(Options { auto_flag, auto_open, ..Options::default() });
+        let detonated = run_assists(&mut minefield, &mut stats);
+        (minefield, stats, detonated)
+    }
+
+    #[test]
+    fn auto_flag_only_flags_proven_mines() {
+        let (minefield, stats, detonated) = assisted(STRIP, true, false);
+        assert!(!detonated && stats.assisted);
+        assert!(minefield.is_flagged(Pos::new(0, 0)));
+        assert_eq!(minefield.flag_count(), 1);
+        // Auto-flag never reveals anything.
+        assert!(!minefield.is_revealed(Pos::new(3, 0)));
+
+        // With nothing proven, it does nothing at all.
+        let (minefield, stats, _) = assisted("minesweeper 3 1 1\n.*.\n", true, false);
+        assert_eq!(minefield.flag_count(), 0);
+        assert!(!stats.assisted);
+    }
+
+    #[test]
+    fn auto_open_only_opens_proven_safe_cells() {
+        let (minefield, stats, detonated) = assisted(STRIP, false, true);
+        assert!(!detonated && stats.assisted);
+        // The 0 opens its neighbour, which floods to the 1 next to the last mine.
+        assert!(minefield.is_revealed(Pos::new(3, 0)) && minefield.is_revealed(Pos::new(4, 0)));
+        assert_eq!(minefield.exploded_mines().count(), 0);
+        assert_eq!(minefield.flag_count(), 0);
+        assert!(minefield.is_won());
+    }
+
+    #[test]
+    fn assists_ignore_the_players_flags() {
+        // A wrong flag on the safe cell next to the 0 is left for the player to fix.
+        let (minefield, _, detonated) = assisted("minesweeper 6 1 2\n*10f.*\n", true, true);
+        assert!(!detonated);
+        assert!(minefield.is_flagged(Pos::new(3, 0)) && !minefield.is_revealed(Pos::new(3, 0)));
+        assert!(!minefield.is_revealed(Pos::new(4, 0)));
+        assert!(minefield.is_flagged(Pos::new(0, 0)));
+    }
+
+    #[test]
+    fn assist_moves_are_tagged_in_the_history() {
+        let mut minefield = board_file::from_text("minesweeper 6 1 1\n*.....\n").unwrap();
+        let mut stats = Stats::new(Options { auto_flag: true, auto_open: true, ..Options::default() });
+        minefield.apply(Action::Reveal(Pos::new(2, 0)));
+        run_assists(&mut minefield, &mut stats);
+
+        let (_, actions) = minefield.rewind();
+        assert_eq!(actions[0], (Action::Reveal(Pos::new(2, 0)), false));
+        assert!(actions.len() > 1 && actions[1..].iter().all(|&(_, assisted)| assisted));
+
+        // The tag survives an undo and a redo.
+        minefield.undo();
+        minefield.redo();
+        assert_eq!(minefield.rewind().1, actions);
+    }
 }
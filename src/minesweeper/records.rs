@@ -0,0 +1,198 @@
+use super::topology::Topology;
+use crate::records::{self, TsvRecord};
+use std::io;
+
+/// The name of the records file in `~/.games-in-rust`.
+const FILE_NAME: &str = "minesweeper-records.tsv";
+
+/// A single finished Minesweeper game, as stored in the records file.
+#[derive(Clone, Debug)]
+pub struct Record {
+    /// The difficulty label of the board, e.g. `Expert` or `Custom 12x12/20`.
+    pub difficulty: String,
+    /// Whether the game was won.
+    pub won: bool,
+    /// The elapsed time in seconds.
+    pub seconds: f64,
+    /// The 3BV (minimum number of clicks) of the board.
+    pub three_bv: u32,
+    /// The number of clicks the player made.
+    pub clicks: u32,
+    /// Whether the game was a practice game, which is never compared in the records.
+    pub practice: bool,
+    /// Whether an assist flagged or opened cells for the player, which also keeps the
+    /// game out of the comparisons.
+    pub assisted: bool,
+}
+
+impl Record {
+    /// Returns the 3BV solved per second, the usual speed measure that lets times on
+    /// boards of different difficulty be compared fairly.
+    pub fn three_bv_per_second(&self) -> f64 {
+        if self.seconds > 0.0 {
+            self.three_bv as f64 / self.seconds
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns the click efficiency, i.e. the 3BV divided by the clicks made.
+    pub fn efficiency(&self) -> f64 {
+        if self.clicks > 0 {
+            self.three_bv as f64 / self.clicks as f64
+        } else {
+            0.0
+        }
+    }
+}
+
+impl TsvRecord for Record {
+    fn to_line(&self) -> String {
+        format!(
+            "{}\t{}\t{:.3}\t{}\t{}\t{}\t{}",
+            self.difficulty,
+            if self.won { "won" } else { "lost" },
+            self.seconds,
+            self.three_bv,
+            self.clicks,
+            if self.practice { "practice" } else { "normal" },
+            if self.assisted { "assisted" } else { "unassisted" },
+        )
+    }
+
+    fn from_line(line: &str) -> Option<Record> {
+        let mut fields = line.split('\t');
+        Some(Record {
+            difficulty: fields.next()?.to_string(),
+            won: fields.next()? == "won",
+            seconds: fields.next()?.parse().ok()?,
+            three_bv: fields.next()?.parse().ok()?,
+            clicks: fields.next()?.parse().ok()?,
+            // Records written before practice games existed have no such field.
+            practice: fields.next() == Some("practice"),
+            // Nor do records written before the assists existed.
+            assisted: fields.next() == Some("assisted"),
+        })
+    }
+}
+
+/// Returns the difficulty label for a board of the given size and topology.
+///
+/// The three classic presets are named, any other board is labelled with its size
+/// so that records are only ever compared against identical boards. Boards with a
+/// topology other than the classic one have it added, e.g. `Expert (hex torus)`.
+pub fn difficulty_label(width: usize, height: usize, mines: usize, topology: Topology) -> String {
+    let label = match (width, height, mines) {
+        (9, 9, 10) => "Beginner".to_string(),
+        (16, 16, 40) => "Intermediate".to_string(),
+        (30, 16, 99) => "Expert".to_string(),
+        _ => format!("Custom {}x{}/{}", width, height, mines),
+    };
+    if topology == Topology::default() {
+        label
+    } else {
+        format!("{} ({})", label, topology)
+    }
+}
+
+/// Appends a record to the records file, creating the file if needed.
+pub fn save(record: &Record) -> io::Result<()> {
+    records::save(FILE_NAME, record)
+}
+
+/// Loads every record stored in the records file.
+///
+/// A missing file simply means no games have been recorded yet.
+pub fn load() -> Vec<Record> {
+    records::load(FILE_NAME)
+}
+
+/// Returns the best won game for a difficulty, by time and by 3BV/s. Practice and
+/// assisted games are left out.
+pub fn best(records: &[Record], difficulty: &str) -> (Option<Record>, Option<Record>) {
+    let wins = records.iter().filter(|r| r.won && !r.practice && !r.assisted && r.difficulty == difficulty);
+    let fastest = wins.clone().min_by(|a, b| a.seconds.total_cmp(&b.seconds)).cloned();
+    let quickest = wins
+        .max_by(|a, b| a.three_bv_per_second().total_cmp(&b.three_bv_per_second()))
+        .cloned();
+    (fastest, quickest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::minesweeper::topology::Neighbourhood;
+
+    /// Returns a won, unassisted game on the given board.
+    fn game(difficulty: &str, seconds: f64, three_bv: u32, clicks: u32) -> Record {
+        Record {
+            difficulty: difficulty.to_string(),
+            won: true,
+            seconds,
+            three_bv,
+            clicks,
+            practice: false,
+            assisted: false,
+        }
+    }
+
+    #[test]
+    fn speed_and_efficiency_are_measured_against_the_3bv() {
+        let record = game("Beginner", 8.0, 20, 25);
+        assert_eq!(record.three_bv_per_second(), 2.5);
+        assert_eq!(record.efficiency(), 0.8);
+        // An instant game or one without clicks does not divide by zero.
+        assert_eq!(game("Beginner", 0.0, 20, 0).three_bv_per_second(), 0.0);
+        assert_eq!(game("Beginner", 0.0, 20, 0).efficiency(), 0.0);
+    }
+
+    #[test]
+    fn best_skips_losses_practice_and_assisted_games_and_other_boards() {
+        let mut lost = game("Expert", 50.0, 150, 160);
+        lost.won = false;
+        let mut practice = game("Expert", 40.0, 150, 160);
+        practice.practice = true;
+        let mut assisted = game("Expert", 30.0, 150, 160);
+        assisted.assisted = true;
+        let records = [
+            lost,
+            practice,
+            assisted,
+            game("Intermediate", 20.0, 60, 70),
+            game("Expert", 100.0, 120, 130),
+            game("Expert", 90.0, 45, 60),
+        ];
+
+        let (fastest, quickest) = best(&records, "Expert");
+        assert_eq!(fastest.map(|r| r.seconds), Some(90.0));
+        // The slower game had a harder board, so it solved more 3BV per second.
+        assert_eq!(quickest.map(|r| r.seconds), Some(100.0));
+        assert!(matches!(best(&records, "Beginner"), (None, None)));
+    }
+
+    #[test]
+    fn records_survive_a_round_trip_through_a_line() {
+        let mut record = game("Custom 12x12/20", 61.25, 30, 41);
+        record.practice = true;
+        let line = record.to_line();
+        assert_eq!(line, "Custom 12x12/20\twon\t61.250\t30\t41\tpractice\tunassisted");
+        let read = Record::from_line(&line).unwrap();
+        assert_eq!(read.difficulty, "Custom 12x12/20");
+        assert!(read.won && read.practice && !read.assisted);
+        assert_eq!((read.seconds, read.three_bv, read.clicks), (61.25, 30, 41));
+
+        // Records written before practice games and assists existed still load.
+        let old = Record::from_line("Expert\tlost\t12.000\t120\t30").unwrap();
+        assert!(!old.won && !old.practice && !old.assisted);
+        assert!(Record::from_line("Expert\tlost\tslow\t120\t30").is_none());
+    }
+
+    #[test]
+    fn the_classic_boards_are_named() {
+        assert_eq!(difficulty_label(9, 9, 10, Topology::default()), "Beginner");
+        assert_eq!(difficulty_label(30, 16, 99, Topology::default()), "Expert");
+        assert_eq!(difficulty_label(30, 16, 98, Topology::default()), "Custom 30x16/98");
+        let hex = Topology { neighbourhood: Neighbourhood::Hex, wrap: true, layers: 1 };
+        assert_eq!(difficulty_label(30, 16, 99, hex), "Expert (hex torus)");
+    }
+}
@@ -0,0 +1,629 @@
+use super::utils::{Minefield, Pos};
+use std::collections::{HashMap, HashSet};
+
+/// The most partial arrangements tried for one group of frontier cells before the
+/// solver gives up on exact probabilities for it and estimates them instead.
+const ENUMERATION_LIMIT: usize = 200_000;
+
+/// A cell whose contents follow logically from what the player can see.
+#[derive(Clone, Debug)]
+pub struct Deduction {
+    /// The deduced cell.
+    pub pos: Pos,
+    /// Whether the cell is a mine, as opposed to safe.
+    pub mine: bool,
+    /// A sentence explaining why, for hints and the tutorial.
+    pub reason: String,
+}
+
+/// Everything the solver knows about a board from the player's point of view.
+pub struct Analysis {
+    /// Every cell that can be proven safe or a mine, in the order they were found.
+    pub deductions: Vec<Deduction>,
+    /// The chance of a mine under every other hidden cell.
+    pub probabilities: HashMap<Pos, f64>,
+}
+
+impl Analysis {
+    /// Returns whether any hidden cell is proven safe.
+    pub fn has_safe(&self) -> bool {
+        self.deductions.iter().any(|d| !d.mine)
+    }
+
+    /// Returns the chance that revealing `pos` hits a mine: 0 or 1 for deduced
+    /// cells, the computed probability for the others.
+    pub fn mine_probability(&self, pos: Pos) -> f64 {
+        match self.deductions.iter().find(|d| d.pos == pos) {
+            Some(deduction) => f64::from(u8::from(deduction.mine)),
+            None => self.probabilities.get(&pos).copied().unwrap_or(0.0),
+        }
+    }
+
+    /// Returns the hidden cell least likely to be a mine, for when nothing is safe.
+    pub fn best_guess(&self) -> Option<Pos> {
+        self.probabilities
+            .iter()
+            .min_by(|a, b| a.1.total_cmp(b.1).then((a.0.y, a.0.x).cmp(&(b.0.y, b.0.x))))
+            .map(|(&pos, _)| pos)
+    }
+}
+
+/// A revealed number, reduced to the hidden cells around it that are not yet
+/// deduced and the number of mines still missing among them.
+struct Constraint {
+    /// The revealed cell the number is in.
+    origin: Pos,
+    /// The undecided hidden neighbours, sorted so constraints can be compared.
+    cells: Vec<Pos>,
+    /// The mines among `cells`.
+    mines: usize,
+}
+
+/// Describes a cell in explanations, as `the 2 at (4, 7)`.
+fn describe(minefield: &Minefield, pos: Pos) -> String {
+    format!("the {} at ({}, {})", minefield.adjacent_mines(pos), pos.x, pos.y)
+}
+
+/// Returns whether the player can see that `pos` is a mine, i.e. it was revealed by
+/// setting it off, or it has been deduced as one.
+fn known_mine(minefield: &Minefield, known: &HashMap<Pos, bool>, pos: Pos) -> bool {
+    (minefield.is_revealed(pos) && minefield.is_mine(pos)) || known.get(&pos) == Some(&true)
+}
+
+/// Builds the constraint of every revealed number that still has undecided neighbours.
+///
+/// Only what the player can see is used: revealed cells and their numbers. Flags are
+/// ignored, since they might be wrong.
+fn constraints(minefield: &Minefield, known: &HashMap<Pos, bool>) -> Vec<Constraint> {
+    let mut constraints = Vec::new();
+    for origin in minefield.positions() {
+        if !minefield.is_revealed(origin) || minefield.is_mine(origin) {
+            continue;
+        }
+        let mut cells = Vec::new();
+        let mut found = 0;
+        for n in minefield.neighbours(origin) {
+            if known_mine(minefield, known, n) {
+                found += 1;
+            } else if !minefield.is_revealed(n) && !known.contains_key(&n) {
+                cells.push(n);
+            }
+        }
+        if cells.is_empty() {
+            continue;
+        }
+        cells.sort_by_key(|p| (p.y, p.x));
+        let mines = usize::from(minefield.adjacent_mines(origin)).saturating_sub(found);
+        constraints.push(Constraint { origin, cells, mines });
+    }
+    constraints
+}
+
+/// Applies the single number rule: a number whose missing mines equal its undecided
+/// neighbours has only mines left around it, and one with no missing mines has only
+/// safe cells left around it.
+fn single_number(minefield: &Minefield, constraints: &[Constraint], known: &HashMap<Pos, bool>) -> Vec<Deduction> {
+    let mut found = Vec::new();
+    let mut seen = HashSet::new();
+    for c in constraints {
+        let mine = if c.mines == 0 {
+            false
+        } else if c.mines == c.cells.len() {
+            true
+        } else {
+            continue;
+        };
+        let reason = if mine {
+            format!(
+                "{} needs {} more mine{} and has only that many hidden cells left, so they are all mines.",
+                describe(minefield, c.origin),
+                c.mines,
+                if c.mines == 1 { "" } else { "s" }
+            )
+        } else {
+            format!(
+                "{} already touches all of its mines, so its other hidden cells are safe.",
+                describe(minefield, c.origin)
+            )
+        };
+        for &pos in &c.cells {
+            if !known.contains_key(&pos) && seen.insert(pos) {
+                found.push(Deduction { pos, mine, reason: reason.clone() });
+            }
+        }
+    }
+    found
+}
+
+/// Applies the pair rule to every two numbers sharing hidden cells.
+///
+/// The first number bounds how many mines can be in the shared cells: no more than
+/// it has, and no fewer than it cannot fit in its other cells. If the second number
+/// needs exactly as many mines as the shared cells cannot hold, its other cells are
+/// all mines, and if the shared cells already hold all it needs, they are all safe.
+/// This is how patterns such as 1-1, 1-2 and 1-2-1 are solved.
+fn pairs(minefield: &Minefield, constraints: &[Constraint]) -> Vec<Deduction> {
+    // Index the constraints by cell, so only numbers sharing cells are compared.
+    let mut by_cell: HashMap<Pos, Vec<usize>> = HashMap::new();
+    for (i, c) in constraints.iter().enumerate() {
+        for &pos in &c.cells {
+            by_cell.entry(pos).or_default().push(i);
+        }
+    }
+
+    let mut found = Vec::new();
+    let mut seen = HashSet::new();
+    for (i, first) in constraints.iter().enumerate() {
+        let mut candidates: Vec<usize> = first.cells.iter().flat_map(|pos| by_cell[pos].iter().copied()).collect();
+        candidates.sort_unstable();
+        candidates.dedup();
+        for j in candidates.into_iter().filter(|&j| j != i) {
+            let second = &constraints[j];
+            let shared = first.cells.iter().filter(|pos| second.cells.contains(pos)).count();
+            let rest: Vec<Pos> = second.cells.iter().filter(|pos| !first.cells.contains(pos)).copied().collect();
+            if rest.is_empty() {
+                continue;
+            }
+
+            // The fewest and most mines the shared cells can hold.
+            let least = first.mines.saturating_sub(first.cells.len() - shared);
+            let most = first.mines.min(shared).min(second.mines);
+            let (mine, reason) = if second.mines - most == rest.len() {
+                (
+                    true,
+                    format!(
+                        "{} allows at most {} mine{} in the cells it shares with {}, so the other hidden cells of that number are all mines.",
+                        describe(minefield, first.origin),
+                        most,
+                        if most == 1 { "" } else { "s" },
+                        describe(minefield, second.origin)
+                    ),
+                )
+            } else if second.mines <= least {
+                (
+                    false,
+                    format!(
+                        "{} must have at least {} mine{} in the cells it shares with {}, which is all that number needs, so its other hidden cells are safe.",
+                        describe(minefield, first.origin),
+                        least,
+                        if least == 1 { "" } else { "s" },
+                        describe(minefield, second.origin)
+                    ),
+                )
+            } else {
+                continue;
+            };
+            for pos in rest {
+                if seen.insert(pos) {
+                    found.push(Deduction { pos, mine, reason: reason.clone() });
+                }
+            }
+        }
+    }
+    found
+}
+
+/// Returns the deductions that follow from single numbers on their own, without
+/// comparing numbers with each other. The mines a number proves count for the
+/// numbers next to them, so a number whose mines have all been found proves the
+/// rest of its cells safe. These are the moves a player should never miss.
+pub fn trivial(minefield: &Minefield) -> Vec<Deduction> {
+    let mut deductions = Vec::new();
+    let mut known: HashMap<Pos, bool> = HashMap::new();
+    loop {
+        let found = single_number(minefield, &constraints(minefield, &known), &known);
+        if found.is_empty() {
+            return deductions;
+        }
+        for deduction in found {
+            known.insert(deduction.pos, deduction.mine);
+            deductions.push(deduction);
+        }
+    }
+}
+
+/// Finds every cell that can be proven safe or a mine by repeatedly applying the
+/// single number and pair rules, and then by counting every arrangement of the
+/// remaining mines.
+///
+/// # Arguments
+///
+/// * `minefield`: The board, of which only what the player can see is used.
+///
+/// # Returns
+///
+/// The deductions, and the chance of a mine under every other hidden cell.
+pub fn analyse(minefield: &Minefield) -> Analysis {
+    let mut deductions = Vec::new();
+    let mut known: HashMap<Pos, bool> = HashMap::new();
+
+    // Apply the cheap rules until they stop finding anything.
+    loop {
+        let constraints = constraints(minefield, &known);
+        let mut found = single_number(minefield, &constraints, &known);
+        if found.is_empty() {
+            found = pairs(minefield, &constraints);
+        }
+        if found.is_empty() {
+            break;
+        }
+        for deduction in found {
+            known.insert(deduction.pos, deduction.mine);
+            deductions.push(deduction);
+        }
+    }
+
+    // Count the arrangements of the remaining mines. Any cell that is a mine in all
+    // of them, or in none, is deduced too.
+    let (probabilities, exact) = probabilities(minefield, &known);
+    let mut remaining = HashMap::new();
+    for (pos, p) in probabilities {
+        // Allow for rounding when every arrangement has a mine in the cell.
+        let mine = p > 1.0 - 1e-9;
+        if exact && (p <= 0.0 || mine) {
+            deductions.push(Deduction {
+                pos,
+                mine,
+                reason: format!(
+                    "Every way of placing the remaining mines that fits the numbers {} a mine at ({}, {}).",
+                    if mine { "puts" } else { "leaves out" },
+                    pos.x,
+                    pos.y
+                ),
+            });
+        } else {
+            remaining.insert(pos, p);
+        }
+    }
+    Analysis { deductions, probabilities: remaining }
+}
+
+/// Returns the natural logarithm of the binomial coefficient `n` choose `k`.
+fn ln_choose(n: usize, k: usize) -> f64 {
+    let k = k.min(n - k);
+    (0..k).map(|i| ((n - i) as f64).ln() - ((i + 1) as f64).ln()).sum()
+}
+
+/// The arrangements of mines in one group of frontier cells that are linked by
+/// shared numbers.
+struct Component {
+    /// The cells of the group.
+    cells: Vec<Pos>,
+    /// For each number of mines, how many arrangements use that many.
+    arrangements: Vec<f64>,
+    /// For each number of mines, how many of those arrangements have a mine in each cell.
+    mine_counts: Vec<Vec<f64>>,
+}
+
+/// Counts the arrangements of mines in a group of cells that satisfy its numbers,
+/// trying cells one at a time and backing out as soon as a number is broken.
+///
+/// # Returns
+///
+/// The component, or `None` if more than `ENUMERATION_LIMIT` steps were needed.
+fn enumerate(cells: Vec<Pos>, constraints: &[&Constraint]) -> Option<Component> {
+    let index: HashMap<Pos, usize> = cells.iter().enumerate().map(|(i, &p)| (p, i)).collect();
+    let members: Vec<Vec<usize>> = constraints.iter().map(|c| c.cells.iter().map(|p| index[p]).collect()).collect();
+    let mut of_cell = vec![Vec::new(); cells.len()];
+    for (c, m) in members.iter().enumerate() {
+        for &i in m {
+            of_cell[i].push(c);
+        }
+    }
+
+    struct Search<'a> {
+        of_cell: &'a [Vec<usize>],
+        needed: Vec<usize>,
+        placed: Vec<usize>,
+        left: Vec<usize>,
+        mines: Vec<bool>,
+        arrangements: Vec<f64>,
+        mine_counts: Vec<Vec<f64>>,
+        steps: usize,
+    }
+
+    impl Search<'_> {
+        /// Tries both contents of cell `i` and every cell after it.
+        fn visit(&mut self, i: usize) -> bool {
+            self.steps += 1;
+            if self.steps > ENUMERATION_LIMIT {
+                return false;
+            }
+            if i == self.mines.len() {
+                let k = self.mines.iter().filter(|&&m| m).count();
+                self.arrangements[k] += 1.0;
+                for (j, &m) in self.mines.iter().enumerate() {
+                    if m {
+                        self.mine_counts[k][j] += 1.0;
+                    }
+                }
+                return true;
+            }
+            for mine in [false, true] {
+                // A mine may not exceed any number, and a safe cell may not leave a
+                // number unable to reach its count.
+                let fits = self.of_cell[i].iter().all(|&c| {
+                    if mine {
+                        self.placed[c] < self.needed[c]
+                    } else {
+                        self.placed[c] + self.left[c] > self.needed[c]
+                    }
+                });
+                if !fits {
+                    continue;
+                }
+                for &c in &self.of_cell[i] {
+                    self.left[c] -= 1;
+                    self.placed[c] += usize::from(mine);
+                }
+                self.mines[i] = mine;
+                let finished = self.visit(i + 1);
+                for &c in &self.of_cell[i] {
+                    self.left[c] += 1;
+                    self.placed[c] -= usize::from(mine);
+                }
+                self.mines[i] = false;
+                if !finished {
+                    return false;
+                }
+            }
+            true
+        }
+    }
+
+    let n = cells.len();
+    let mut search = Search {
+        of_cell: &of_cell,
+        needed: constraints.iter().map(|c| c.mines).collect(),
+        placed: vec![0; constraints.len()],
+        left: members.iter().map(Vec::len).collect(),
+        mines: vec![false; n],
+        arrangements: vec![0.0; n + 1],
+        mine_counts: vec![vec![0.0; n]; n + 1],
+        steps: 0,
+    };
+    if !search.visit(0) {
+        return None;
+    }
+    let (arrangements, mine_counts) = (search.arrangements, search.mine_counts);
+    Some(Component { cells, arrangements, mine_counts })
+}
+
+/// Estimates the chances of a group of cells too large to enumerate, from the
+/// highest local chance given by any of its numbers, as a single arrangement.
+fn estimate(cells: Vec<Pos>, constraints: &[&Constraint]) -> Component {
+    let chances: Vec<f64> = cells
+        .iter()
+        .map(|pos| {
+            constraints
+                .iter()
+                .filter(|c| c.cells.contains(pos))
+                .map(|c| c.mines as f64 / c.cells.len() as f64)
+                .fold(0.0, f64::max)
+        })
+        .collect();
+    let k = (chances.iter().sum::<f64>().round() as usize).min(cells.len());
+    let mut arrangements = vec![0.0; cells.len() + 1];
+    arrangements[k] = 1.0;
+    let mut mine_counts = vec![vec![0.0; cells.len()]; cells.len() + 1];
+    mine_counts[k] = chances;
+    Component { cells, arrangements, mine_counts }
+}
+
+/// Multiplies two distributions of mine counts.
+fn convolve(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut out = vec![0.0; a.len() + b.len() - 1];
+    for (i, &x) in a.iter().enumerate() {
+        for (j, &y) in b.iter().enumerate() {
+            out[i + j] += x * y;
+        }
+    }
+    out
+}
+
+/// Computes the chance of a mine under every undecided hidden cell.
+///
+/// The frontier cells, those next to a number, are split into groups linked by
+/// shared numbers, and the arrangements of each group are counted. The groups are
+/// then combined with the number of ways to place the other mines in the cells
+/// away from every number, so every arrangement of the whole board counts equally.
+///
+/// # Returns
+///
+/// The chances, and whether they are exact.
+fn probabilities(minefield: &Minefield, known: &HashMap<Pos, bool>) -> (HashMap<Pos, f64>, bool) {
+    let constraints = constraints(minefield, known);
+
+    // Group the frontier cells by the numbers they share.
+    let mut group_of: HashMap<Pos, usize> = HashMap::new();
+    let mut groups: Vec<(Vec<Pos>, Vec<&Constraint>)> = Vec::new();
+    let mut assigned = vec![false; constraints.len()];
+    for start in 0..constraints.len() {
+        if assigned[start] {
+            continue;
+        }
+        let id = groups.len();
+        let mut cells = Vec::new();
+        let mut members = Vec::new();
+        let mut stack = vec![start];
+        assigned[start] = true;
+        while let Some(c) = stack.pop() {
+            members.push(&constraints[c]);
+            for &pos in &constraints[c].cells {
+                if group_of.insert(pos, id).is_none() {
+                    cells.push(pos);
+                }
+            }
+            for (other, done) in assigned.iter_mut().enumerate() {
+                if !*done && constraints[other].cells.iter().any(|p| group_of.get(p) == Some(&id)) {
+                    *done = true;
+                    stack.push(other);
+                }
+            }
+        }
+        groups.push((cells, members));
+    }
+
+    let mut exact = true;
+    let components: Vec<Component> = groups
+        .into_iter()
+        .map(|(cells, members)| {
+            enumerate(cells.clone(), &members).unwrap_or_else(|| {
+                exact = false;
+                estimate(cells, &members)
+            })
+        })
+        .collect();
+
+    // The hidden cells away from every number share the remaining mines equally.
+    let known_mines = minefield.positions().filter(|&p| known_mine(minefield, known, p)).count();
+    let remaining = minefield.mine_count().saturating_sub(known_mines);
+    let interior: Vec<Pos> = minefield
+        .positions()
+        .filter(|&p| !minefield.is_revealed(p) && !known.contains_key(&p) && !group_of.contains_key(&p))
+        .collect();
+
+    // Weigh each total of frontier mines by the ways to place the rest in the interior,
+    // scaled so the largest weight is 1 to stay within floating point range.
+    let frontier_max: usize = components.iter().map(|c| c.cells.len()).sum();
+    let ln_weights: Vec<Option<f64>> = (0..=frontier_max)
+        .map(|k| {
+            let rest = remaining.checked_sub(k)?;
+            (rest <= interior.len()).then(|| ln_choose(interior.len(), rest))
+        })
+        .collect();
+    let top = ln_weights.iter().flatten().copied().fold(f64::MIN, f64::max);
+    let weights: Vec<f64> = ln_weights.iter().map(|w| w.map_or(0.0, |w| (w - top).exp())).collect();
+
+    let all = components.iter().fold(vec![1.0], |acc, c| convolve(&acc, &c.arrangements));
+    let total: f64 = all.iter().zip(&weights).map(|(a, w)| a * w).sum();
+
+    let mut chances = HashMap::new();
+    if total <= 0.0 || !total.is_finite() {
+        // The visible numbers contradict each other, so give every cell the same chance.
+        let hidden: Vec<Pos> = components.iter().flat_map(|c| c.cells.iter().copied()).chain(interior).collect();
+        let p = remaining as f64 / hidden.len().max(1) as f64;
+        return (hidden.into_iter().map(|pos| (pos, p)).collect(), false);
+    }
+
+    for (c, component) in components.iter().enumerate() {
+        let others = components
+            .iter()
+            .enumerate()
+            .filter(|&(o, _)| o != c)
+            .fold(vec![1.0], |acc, (_, o)| convolve(&acc, &o.arrangements));
+        for (j, &pos) in component.cells.iter().enumerate() {
+            let mut p = 0.0;
+            for (k, counts) in component.mine_counts.iter().enumerate() {
+                if counts[j] == 0.0 {
+                    continue;
+                }
+                for (m, &ways) in others.iter().enumerate() {
+                    p += counts[j] * ways * weights[k + m];
+                }
+            }
+            chances.insert(pos, p / total);
+        }
+    }
+    if !interior.is_empty() {
+        let expected: f64 = all
+            .iter()
+            .enumerate()
+            .map(|(k, a)| a * weights[k] * remaining.saturating_sub(k) as f64)
+            .sum::<f64>()
+            / total;
+        let p = expected / interior.len() as f64;
+        for pos in interior {
+            chances.insert(pos, p);
+        }
+    }
+    (chances, exact)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::minesweeper::board_file;
+
+    /// The 1-1 pattern, with its safe cells at (2, 1) and (5, 1).
+    const ONE_ONE: &str = "minesweeper 8 3 5\n..*..*..\n.*..*.*.\n11111211\n";
+    /// The 1-2 pattern, with its mines at (3, 1) and (4, 1).
+    const ONE_TWO: &str = "minesweeper 8 3 5\n...*....\n*..**..*\n11122111\n";
+    /// The 1-2-1 pattern, with its mines at (3, 1) and (5, 1) and a safe cell between them.
+    const ONE_TWO_ONE: &str = "minesweeper 9 3 5\n......*..\n*..*.*..*\n111121111\n";
+    /// The 1-2-2-1 pattern, with its mines at (3, 1) and (4, 1) and safe cells either side.
+    const ONE_TWO_TWO_ONE: &str = "minesweeper 10 3 6\n....*.....\n*..**..*.*\n.112211121\n";
+    /// An inside corner, with its mine at (3, 1) and safe cells at (3, 0) and (4, 1).
+    const CORNER: &str = "minesweeper 6 3 3\n001..*\n001*.*\n001121\n";
+
+    /// Returns what the solver proves about each of `cells`, as whether it is a mine,
+    /// after checking that everything it proves is true of the board.
+    fn proven(deductions: &[Deduction], minefield: &Minefield, cells: &[(usize, usize)]) -> Vec<Option<bool>> {
+        for deduction in deductions {
+            assert_eq!(deduction.mine, minefield.is_mine(deduction.pos), "{}", deduction.reason);
+        }
+        cells
+            .iter()
+            .map(|&(x, y)| deductions.iter().find(|d| d.pos == Pos::new(x, y)).map(|d| d.mine))
+            .collect()
+    }
+
+    #[test]
+    fn the_pair_rule_solves_the_classic_patterns() {
+        // Each goal is a cell of the pattern and whether it is a mine.
+        let patterns = [
+            (ONE_ONE, vec![(2, 1, false), (5, 1, false)]),
+            (ONE_TWO, vec![(3, 1, true), (4, 1, true)]),
+            (ONE_TWO_ONE, vec![(3, 1, true), (5, 1, true), (4, 1, false)]),
+            (ONE_TWO_TWO_ONE, vec![(3, 1, true), (4, 1, true), (2, 1, false), (5, 1, false)]),
+            (CORNER, vec![(3, 1, true), (3, 0, false), (4, 1, false)]),
+        ];
+        for (text, goals) in patterns {
+            let minefield = board_file::from_text(text).unwrap();
+            let analysis = analyse(&minefield);
+            let cells: Vec<(usize, usize)> = goals.iter().map(|&(x, y, _)| (x, y)).collect();
+            let expected: Vec<Option<bool>> = goals.iter().map(|&(_, _, mine)| Some(mine)).collect();
+            assert_eq!(proven(&analysis.deductions, &minefield, &cells), expected, "{}", text);
+
+            // The patterns are solved by the rules, without counting arrangements.
+            for (x, y) in cells {
+                let deduction = analysis.deductions.iter().find(|d| d.pos == Pos::new(x, y)).unwrap();
+                assert!(!deduction.reason.starts_with("Every way"), "{}", deduction.reason);
+            }
+        }
+    }
+
+    #[test]
+    fn single_numbers_chain_through_the_mines_they_prove() {
+        let minefield = board_file::from_text(CORNER).unwrap();
+        let deductions = trivial(&minefield);
+        let cells = [(3, 1), (3, 0), (4, 1)];
+        assert_eq!(proven(&deductions, &minefield, &cells), [Some(true), Some(false), Some(false)]);
+    }
+
+    #[test]
+    fn single_numbers_alone_do_not_solve_the_one_one() {
+        let minefield = board_file::from_text(ONE_ONE).unwrap();
+        let deductions = trivial(&minefield);
+        assert_eq!(proven(&deductions, &minefield, &[(2, 1), (5, 1)]), [None, None]);
+    }
+
+    #[test]
+    fn flags_are_not_trusted() {
+        // A wrong flag next to the 1 must not make its real mine look safe.
+        let minefield = board_file::from_text("minesweeper 3 1 1\nf1*\n").unwrap();
+        let analysis = analyse(&minefield);
+        assert_eq!(proven(&analysis.deductions, &minefield, &[(0, 0), (2, 0)]), [None, None]);
+    }
+
+    #[test]
+    fn undecided_cells_share_the_remaining_mines() {
+        // The 1 has two hidden cells and nothing tells them apart.
+        let minefield = board_file::from_text("minesweeper 3 1 1\n*1.\n").unwrap();
+        let analysis = analyse(&minefield);
+        assert!(analysis.deductions.is_empty());
+        assert!((analysis.mine_probability(Pos::new(0, 0)) - 0.5).abs() < 1e-9);
+        assert!((analysis.mine_probability(Pos::new(2, 0)) - 0.5).abs() < 1e-9);
+        assert_eq!(analysis.best_guess(), Some(Pos::new(0, 0)));
+    }
+}
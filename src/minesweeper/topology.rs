@@ -0,0 +1,297 @@
+use super::utils::Pos;
+use std::fmt;
+
+/// The offsets of the 8 cells around a cell on a square grid.
+const KING: [(isize, isize); 8] = [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)];
+/// The offsets of the 4 cells sharing an edge with a cell on a square grid.
+const ORTHOGONAL: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+/// The offsets of the 8 cells a chess knight can jump to.
+const KNIGHT: [(isize, isize); 8] = [(-1, -2), (1, -2), (-2, -1), (2, -1), (-2, 1), (2, 1), (-1, 2), (1, 2)];
+/// The offsets of the 6 cells around a cell in an even row of a hexagonal grid.
+const HEX_EVEN_ROW: [(isize, isize); 6] = [(-1, -1), (0, -1), (-1, 0), (1, 0), (-1, 1), (0, 1)];
+/// The offsets of the 6 cells around a cell in an odd row of a hexagonal grid.
+const HEX_ODD_ROW: [(isize, isize); 6] = [(0, -1), (1, -1), (-1, 0), (1, 0), (0, 1), (1, 1)];
+
+/// The smallest width and height of a wrap-around board, so that no cell is ever its
+/// own neighbour or counted twice as a neighbour.
+pub const MIN_WRAP_SIZE: usize = 5;
+
+/// Which cells count as the neighbours of a cell.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Neighbourhood {
+    /// The classic 8 surrounding cells of a square grid.
+    King,
+    /// Only the 4 cells sharing an edge.
+    Orthogonal,
+    /// The 8 cells a chess knight can jump to.
+    Knight,
+    /// The 6 surrounding cells of a hexagonal grid, drawn with every odd row shifted
+    /// half a cell to the right.
+    Hex,
+}
+
+/// The shape of a minefield: which cells are neighbours, whether the edges wrap
+/// around so the board becomes a torus, and how many layers are stacked on top of
+/// each other.
+///
+/// A layered board is stored as its layers placed one below the other, so a board
+/// of 4 layers of 10 rows has 40 rows, and rows 10 to 19 make up the second layer.
+/// This lets layered boards share the generation, flood fill and file format of
+/// flat ones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Topology {
+    pub neighbourhood: Neighbourhood,
+    pub wrap: bool,
+    /// The number of layers, 1 for a flat board.
+    pub layers: usize,
+}
+
+impl Default for Topology {
+    /// The classic board: 8 neighbours, clipped at the edges, on a single layer.
+    fn default() -> Topology {
+        Topology { neighbourhood: Neighbourhood::King, wrap: false, layers: 1 }
+    }
+}
+
+impl Neighbourhood {
+    /// Returns the name used for the neighbourhood on the command line and in board files.
+    pub fn name(self) -> &'static str {
+        match self {
+            Neighbourhood::King => "king",
+            Neighbourhood::Orthogonal => "orthogonal",
+            Neighbourhood::Knight => "knight",
+            Neighbourhood::Hex => "hex",
+        }
+    }
+
+    /// Parses a neighbourhood from its name.
+    pub fn from_name(name: &str) -> Option<Neighbourhood> {
+        [Neighbourhood::King, Neighbourhood::Orthogonal, Neighbourhood::Knight, Neighbourhood::Hex]
+            .into_iter()
+            .find(|n| n.name() == name)
+    }
+}
+
+impl Topology {
+    /// Checks that a board of the given size can use this topology.
+    ///
+    /// # Returns
+    ///
+    /// A message describing the problem if the rows cannot be split evenly into layers,
+    /// if the board is too small to wrap around, or if a wrapped hexagonal board has an
+    /// odd number of rows, which would not line up.
+    ///
+    /// The height is that of the whole board, i.e. of all its layers together.
+    pub fn check(self, width: usize, height: usize) -> Result<(), String> {
+        if self.layers == 0 || self.layer_height(height) * self.layers != height {
+            return Err("The board must have at least one layer, and every layer the same height.".to_string());
+        }
+        let height = self.layer_height(height);
+        if self.wrap && (width < MIN_WRAP_SIZE || height < MIN_WRAP_SIZE) {
+            return Err(format!(
+                "Wrap-around boards must be at least {0}x{0}.",
+                MIN_WRAP_SIZE
+            ));
+        }
+        if self.wrap && self.neighbourhood == Neighbourhood::Hex && height % 2 == 1 {
+            return Err("Wrap-around hexagonal boards must have an even number of rows.".to_string());
+        }
+        Ok(())
+    }
+
+    /// Returns the number of rows in each layer of a board with `height` rows in total.
+    pub fn layer_height(self, height: usize) -> usize {
+        height / self.layers.max(1)
+    }
+
+    /// Returns how far `to` is from `from` along one axis of a layer `length` cells
+    /// long.
+    ///
+    /// On a wrap-around board the distance is taken the short way round, so a
+    /// neighbour across an edge is one step away rather than the whole board.
+    pub fn offset(self, from: usize, to: usize, length: usize) -> isize {
+        let d = to as isize - from as isize;
+        let half = length as isize / 2;
+        match self.wrap {
+            true if d > half => d - length as isize,
+            true if d < -half => d + length as isize,
+            _ => d,
+        }
+    }
+
+    /// Returns the offsets of the neighbours of a cell in the given row.
+    fn offsets(self, y: usize) -> &'static [(isize, isize)] {
+        match self.neighbourhood {
+            Neighbourhood::King => &KING,
+            Neighbourhood::Orthogonal => &ORTHOGONAL,
+            Neighbourhood::Knight => &KNIGHT,
+            Neighbourhood::Hex if y % 2 == 1 => &HEX_ODD_ROW,
+            Neighbourhood::Hex => &HEX_EVEN_ROW,
+        }
+    }
+
+    /// Returns the neighbours of `pos` on a board of the given size.
+    ///
+    /// On a wrap-around board, neighbours past an edge are taken from the opposite
+    /// edge. Otherwise they are clipped.
+    ///
+    /// On a layered board, the neighbours in the layers directly above and below are
+    /// the cell at the same place and that cell's neighbours, so the classic
+    /// neighbourhood grows to all 26 cells of the surrounding cube. Layers never wrap.
+    ///
+    /// # Arguments
+    /// * `width`: The width of the board.
+    /// * `height`: The height of the board, i.e. of all its layers together.
+    /// * `pos`: The cell whose neighbours are wanted.
+    pub fn neighbours(self, width: usize, height: usize, pos: Pos) -> impl Iterator<Item = Pos> {
+        let layer_height = self.layer_height(height);
+        let (w, h) = (width as isize, layer_height as isize);
+        let wrap = self.wrap;
+        let layers = self.layers as isize;
+        let layer = (pos.y / layer_height) as isize;
+        let row = pos.y % layer_height;
+
+        // Visit the own layer first, then the layers above and below, where the cell
+        // directly over `pos` is a neighbour too.
+        [0, -1, 1].into_iter().filter(move |dz| (0..layers).contains(&(layer + dz))).flat_map(move |dz| {
+            let centre: &'static [(isize, isize)] = if dz == 0 { &[] } else { &[(0, 0)] };
+            centre.iter().chain(self.offsets(row)).filter_map(move |&(dx, dy)| {
+                let mut nx = pos.x as isize + dx;
+                let mut ny = row as isize + dy;
+                if wrap {
+                    nx = nx.rem_euclid(w);
+                    ny = ny.rem_euclid(h);
+                }
+
+                // Skip the neighbour if it falls outside the bounds of the layer.
+                if (0..w).contains(&nx) && (0..h).contains(&ny) {
+                    Some(Pos::new(nx as usize, ((layer + dz) * h + ny) as usize))
+                } else {
+                    None
+                }
+            })
+        })
+    }
+}
+
+impl fmt::Display for Topology {
+    /// Formats the topology as its neighbourhood name, followed by `torus` if the
+    /// edges wrap around and by e.g. `layers=4` if the board is layered.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.neighbourhood.name())?;
+        if self.wrap {
+            write!(f, " torus")?;
+        }
+        if self.layers > 1 {
+            write!(f, " layers={}", self.layers)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    /// Returns the number of distinct neighbours of `(x, y)` on an 8x6 board.
+    fn count(neighbourhood: Neighbourhood, wrap: bool, (x, y): (usize, usize)) -> usize {
+        let topology = Topology { neighbourhood, wrap, layers: 1 };
+        let neighbours: HashSet<Pos> = topology.neighbours(8, 6, Pos::new(x, y)).collect();
+        assert!(!neighbours.contains(&Pos::new(x, y)), "{} at ({}, {}) is its own neighbour", topology, x, y);
+        neighbours.len()
+    }
+
+    #[test]
+    fn corners_lose_their_neighbours_past_the_edges() {
+        assert_eq!(count(Neighbourhood::King, false, (0, 0)), 3);
+        assert_eq!(count(Neighbourhood::Orthogonal, false, (0, 0)), 2);
+        assert_eq!(count(Neighbourhood::Knight, false, (0, 0)), 2);
+        assert_eq!(count(Neighbourhood::Hex, false, (0, 0)), 2);
+    }
+
+    #[test]
+    fn interior_cells_have_every_neighbour() {
+        for (x, y) in [(4, 3), (3, 2)] {
+            assert_eq!(count(Neighbourhood::King, false, (x, y)), 8);
+            assert_eq!(count(Neighbourhood::Orthogonal, false, (x, y)), 4);
+            assert_eq!(count(Neighbourhood::Knight, false, (x, y)), 8);
+            assert_eq!(count(Neighbourhood::Hex, false, (x, y)), 6);
+        }
+    }
+
+    #[test]
+    fn wrapping_gives_every_cell_every_neighbour() {
+        for pos in [(0, 0), (7, 5), (4, 3)] {
+            assert_eq!(count(Neighbourhood::King, true, pos), 8);
+            assert_eq!(count(Neighbourhood::Orthogonal, true, pos), 4);
+            assert_eq!(count(Neighbourhood::Knight, true, pos), 8);
+            assert_eq!(count(Neighbourhood::Hex, true, pos), 6);
+        }
+        let corner: HashSet<Pos> = Topology { neighbourhood: Neighbourhood::Orthogonal, wrap: true, layers: 1 }
+            .neighbours(8, 6, Pos::new(0, 0))
+            .collect();
+        assert_eq!(corner, HashSet::from([Pos::new(1, 0), Pos::new(0, 1), Pos::new(7, 0), Pos::new(0, 5)]));
+    }
+
+    #[test]
+    fn hex_rows_lean_towards_the_shifted_side() {
+        let hex = Topology { neighbourhood: Neighbourhood::Hex, wrap: false, layers: 1 };
+        let even: HashSet<Pos> = hex.neighbours(8, 6, Pos::new(3, 2)).collect();
+        assert!(even.contains(&Pos::new(2, 1)) && !even.contains(&Pos::new(4, 1)));
+        let odd: HashSet<Pos> = hex.neighbours(8, 6, Pos::new(3, 3)).collect();
+        assert!(odd.contains(&Pos::new(4, 2)) && !odd.contains(&Pos::new(2, 2)));
+    }
+
+    #[test]
+    fn wrapped_boards_must_be_large_enough() {
+        let torus = Topology { neighbourhood: Neighbourhood::King, wrap: true, layers: 1 };
+        assert!(torus.check(MIN_WRAP_SIZE, MIN_WRAP_SIZE).is_ok());
+        assert_eq!(
+            torus.check(MIN_WRAP_SIZE - 1, MIN_WRAP_SIZE),
+            Err(format!("Wrap-around boards must be at least {0}x{0}.", MIN_WRAP_SIZE))
+        );
+        assert!(torus.check(MIN_WRAP_SIZE, MIN_WRAP_SIZE - 1).is_err());
+        // A flat board of any size is fine.
+        assert!(Topology::default().check(1, 1).is_ok());
+
+        let hex_torus = Topology { neighbourhood: Neighbourhood::Hex, wrap: true, layers: 1 };
+        assert!(hex_torus.check(6, 6).is_ok());
+        assert_eq!(
+            hex_torus.check(6, 7),
+            Err("Wrap-around hexagonal boards must have an even number of rows.".to_string())
+        );
+    }
+
+    #[test]
+    fn offsets_only_wrap_on_a_torus() {
+        let flat = Topology::default();
+        let torus = Topology { wrap: true, ..flat };
+        assert_eq!(flat.offset(0, 7, 8), 7);
+        assert_eq!(torus.offset(0, 7, 8), -1);
+        assert_eq!(flat.offset(7, 0, 8), -7);
+        assert_eq!(torus.offset(7, 0, 8), 1);
+        // Near neighbours are the same either way.
+        assert_eq!(flat.offset(3, 4, 8), torus.offset(3, 4, 8));
+        assert_eq!(flat.offset(4, 3, 8), -1);
+
+        // Every neighbour of a corner on a flat layered board stays next to it in the strip.
+        let layered = Topology { layers: 2, ..flat };
+        let corner = Pos::new(0, 0);
+        let own: Vec<Pos> = layered.neighbours(8, 12, corner).filter(|n| n.y < 6).collect();
+        assert_eq!(own.len(), 3);
+        for neighbour in layered.neighbours(8, 12, corner) {
+            assert!(layered.offset(corner.x, neighbour.x, 8).abs() <= 1);
+            assert!(layered.offset(corner.y % 6, neighbour.y % 6, 6).abs() <= 1);
+        }
+    }
+
+    #[test]
+    fn names_round_trip() {
+        let all = [Neighbourhood::King, Neighbourhood::Orthogonal, Neighbourhood::Knight, Neighbourhood::Hex];
+        for neighbourhood in all {
+            assert_eq!(Neighbourhood::from_name(neighbourhood.name()), Some(neighbourhood));
+        }
+        assert_eq!(Neighbourhood::from_name("bishop"), None);
+    }
+}
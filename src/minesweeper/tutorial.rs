@@ -0,0 +1,279 @@
+use super::board_file;
+use super::solver::{self, Deduction};
+use super::utils::{Action, Minefield, Pos};
+use super::viewport::Viewport;
+use super::{
+    draw_game_state, draw_rulers, end_screen, footer_row, move_cursor, read_action, screen_space, start_screen,
+    MouseButtons,
+};
+use ncurses::*;
+
+/// One step of the tutorial: a hand-made board showing a pattern, and the moves the
+/// pattern proves.
+struct Lesson {
+    /// The name of the pattern, shown above the board.
+    title: &'static str,
+    /// How the pattern works, shown below the board.
+    text: &'static str,
+    /// The board, in the board file format with the player's state.
+    board: &'static str,
+    /// The cells the pattern decides, which must all be flagged or revealed to
+    /// finish the lesson.
+    goals: &'static [(usize, usize)],
+}
+
+/// The lessons, from the simplest pattern to the hardest.
+///
+/// The top row of the line boards is never next to a number, so nothing can be
+/// worked out about it. It only holds a few mines, so counting the mines left does
+/// not give the answer away.
+const LESSONS: [Lesson; 5] = [
+    Lesson {
+        title: "The 1-1 pattern",
+        text: "A 1 against the wall touches two hidden cells, and the 1 beside it touches the same two and one \
+               more. The mine of the second 1 must be in the two cells they share, so its third cell is safe. \
+               Find the two safe cells, one from each wall, and reveal them with Enter.",
+        board: "minesweeper 8 3 5\n\
+                ..*..*..\n\
+                .*..*.*.\n\
+                11111211\n",
+        goals: &[(2, 1), (5, 1)],
+    },
+    Lesson {
+        title: "The 1-2 pattern",
+        text: "A 1 next to a 2 shares two hidden cells with it. The 1 allows at most one mine in them, so the \
+               2 needs another mine in its cell that the 1 does not touch. Flag the two mines this proves with \
+               Space.",
+        board: "minesweeper 8 3 5\n\
+                ...*....\n\
+                *..**..*\n\
+                11122111\n",
+        goals: &[(3, 1), (4, 1)],
+    },
+    Lesson {
+        title: "The 1-2-1 pattern",
+        text: "In a row of 1-2-1, each 1 and the 2 form a 1-2, so the cells under both 1s are mines. That is \
+               all the 2 needs, so the cell under it is safe. Flag the two mines and reveal the safe cell.",
+        board: "minesweeper 9 3 5\n\
+                ......*..\n\
+                *..*.*..*\n\
+                111121111\n",
+        goals: &[(3, 1), (5, 1), (4, 1)],
+    },
+    Lesson {
+        title: "The 1-2-2-1 pattern",
+        text: "In a row of 1-2-2-1, each outer 1 forms a 1-2 with its neighbour, so the cells under both 2s \
+               are mines. Those mines are all the 1s need, so the cells under the 1s are safe. Flag the mines \
+               and reveal the safe cells.",
+        board: "minesweeper 10 3 6\n\
+                ....*.....\n\
+                *..**..*.*\n\
+                .112211121\n",
+        goals: &[(3, 1), (4, 1), (2, 1), (5, 1)],
+    },
+    Lesson {
+        title: "Corners",
+        text: "The 1 at the inside corner of the open area touches only one hidden cell, diagonally, so that \
+               cell is a mine. The numbers beside the corner already touch that mine, so their other hidden \
+               cells are safe. Flag the mine and reveal the two safe cells next to it.",
+        board: "minesweeper 6 3 3\n\
+                001..*\n\
+                001*.*\n\
+                001121\n",
+        goals: &[(3, 1), (3, 0), (4, 1)],
+    },
+];
+
+/// Splits a text into lines no wider than `width`, breaking between words.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![String::new()];
+    for word in text.split_whitespace() {
+        let line = lines.last_mut().unwrap();
+        if !line.is_empty() && line.len() + 1 + word.len() > width {
+            lines.push(word.to_string());
+        } else {
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+    }
+    lines
+}
+
+/// Capitalises the first letter of a solver explanation, which starts mid-sentence
+/// with `the 2 at ...`, so it can stand on its own.
+fn sentence(reason: &str) -> String {
+    let mut chars = reason.chars();
+    chars.next().map_or(String::new(), |first| first.to_uppercase().chain(chars).collect())
+}
+
+/// Returns whether the player has done what a goal cell needs: flagged it if it is a
+/// mine, revealed it otherwise.
+fn goal_done(minefield: &Minefield, pos: Pos) -> bool {
+    if minefield.is_mine(pos) {
+        minefield.is_flagged(pos)
+    } else {
+        minefield.is_revealed(pos)
+    }
+}
+
+/// Checks a move against what the numbers prove, and applies it only if it is right.
+///
+/// Only the goal cells of the lesson are played. The solver can work out the rest of
+/// a small board too, but only by building on moves the lesson is not about.
+///
+/// # Arguments
+///
+/// * `minefield`: The lesson board.
+/// * `goals`: What the starting board proves about each goal cell, and why.
+/// * `action`: The move to check.
+///
+/// # Returns
+///
+/// The message telling the player why the move was right or wrong, or `None` for an
+/// action the tutorial does not judge, such as marking a revealed cell.
+fn judge(minefield: &mut Minefield, goals: &[Deduction], action: Action) -> Option<String> {
+    let (pos, flagging) = match action {
+        Action::Reveal(pos) => (pos, false),
+        Action::Mark(pos) => (pos, true),
+        Action::Chord(_) => return None,
+    };
+    // Only hidden cells are judged. Flags are only ever placed on proven mines, so
+    // they are left alone.
+    if minefield.is_revealed(pos) || minefield.is_flagged(pos) {
+        return None;
+    }
+    let Some(goal) = goals.iter().find(|d| d.pos == pos) else {
+        return Some("That cell is not part of this pattern. Look at the numbers the lesson talks about.".to_string());
+    };
+
+    if goal.mine == flagging {
+        minefield.apply(action);
+        Some(format!("Right! {}", sentence(&goal.reason)))
+    } else {
+        Some(format!(
+            "Careful, that cell is {}. {}",
+            if goal.mine { "a mine" } else { "safe" },
+            sentence(&goal.reason)
+        ))
+    }
+}
+
+/// Works out what the starting board of a lesson proves about each of its goal cells.
+///
+/// The explanations come from the starting board, so they stay those of the pattern
+/// however the player goes through it.
+///
+/// # Returns
+///
+/// The deductions in the order of the goals, or a message if a goal is not proven.
+fn lesson_goals(lesson: &Lesson, minefield: &Minefield) -> Result<Vec<Deduction>, String> {
+    let deductions = solver::analyse(minefield).deductions;
+    lesson
+        .goals
+        .iter()
+        .map(|&(x, y)| {
+            deductions
+                .iter()
+                .find(|d| d.pos == Pos::new(x, y))
+                .cloned()
+                .ok_or_else(|| format!("{}: nothing proves the goal at ({}, {}).", lesson.title, x, y))
+        })
+        .collect()
+}
+
+/// Walks the player through the lessons, one board at a time.
+///
+/// Every move is checked with the solver before it is made: flagging a proven mine
+/// or revealing a proven safe cell is explained and applied, and anything else is
+/// explained and left undone, so the player never sets off a mine. Only the goal
+/// cells of a lesson can be played, and it is finished once all of them are
+/// decided. `h` gives a hint and `q` quits.
+///
+/// # Returns
+///
+/// An error if a lesson board does not parse or does not prove its goals.
+pub fn play() -> Result<(), String> {
+    // Set up every lesson first, so a bad board is reported before the screen is taken.
+    let mut boards = Vec::new();
+    for lesson in &LESSONS {
+        let minefield = board_file::from_text(lesson.board).map_err(|e| format!("{}: {}", lesson.title, e))?;
+        let goals = lesson_goals(lesson, &minefield)?;
+        boards.push((minefield, goals));
+    }
+
+    start_screen();
+    // Nothing moves on its own in the tutorial, so wait for every key.
+    timeout(-1);
+
+    'lessons: for (number, (lesson, (mut minefield, goals))) in LESSONS.iter().zip(boards).enumerate() {
+        let mut cursor = Pos::new(0, minefield.height - 1);
+        let mut viewport = Viewport::new();
+        let mut buttons = MouseButtons { left: false, right: false, chorded: false };
+        let mut message = String::new();
+
+        loop {
+            let done = goals.iter().all(|d| goal_done(&minefield, d.pos));
+            if done {
+                message = format!(
+                    "{} {}",
+                    message,
+                    if number + 1 < LESSONS.len() {
+                        "Well done! Press any key for the next lesson."
+                    } else {
+                        "Well done, that is the whole tutorial! Press any key to quit."
+                    }
+                );
+            }
+
+            erase();
+            viewport.follow((minefield.width, minefield.layer_height()), screen_space(&minefield), cursor);
+            mvaddstr(
+                0,
+                0,
+                &format!("Lesson {}/{}: {}  (h hint, q quit)", number + 1, LESSONS.len(), lesson.title),
+            );
+            draw_rulers(&minefield, &viewport);
+            draw_game_state(&minefield, if done { None } else { Some(cursor) }, &viewport, false);
+            let width = COLS().max(20) as usize;
+            // The explanation of the pattern, then a blank line and the last message.
+            let mut lines = wrap(lesson.text, width);
+            lines.push(String::new());
+            lines.extend(wrap(&message, width));
+            for (row, line) in (footer_row(&minefield, &viewport)..).zip(&lines) {
+                mvaddstr(row, 0, line);
+            }
+            refresh();
+
+            let input = getch();
+            if done {
+                continue 'lessons;
+            }
+            if input == 'q' as i32 {
+                break 'lessons;
+            }
+
+            // h points at an undecided goal and explains it.
+            if input == 'h' as i32 {
+                if let Some(deduction) = goals.iter().find(|d| !goal_done(&minefield, d.pos)) {
+                    cursor = deduction.pos;
+                    message = format!("Hint: look at the cell under the cursor. {}", sentence(&deduction.reason));
+                }
+                continue;
+            }
+
+            if let Some(action) = read_action(&minefield, input, &viewport, &mut cursor, &mut buttons) {
+                if let Some(verdict) = judge(&mut minefield, &goals, action) {
+                    message = verdict;
+                }
+            }
+            move_cursor(&minefield, input, &viewport, &mut cursor);
+        }
+    }
+
+    // Stop reporting mouse events and clean up the ncurses library.
+    end_screen();
+    Ok(())
+}
+
@@ -1,12 +1,92 @@
 extern crate ncurses;
 extern crate rand;
-use rand::Rng;
-use std::collections::HashSet;
+use rand::rngs::StdRng;
+use rand::seq::index;
+use rand::{Rng, SeedableRng};
+use super::topology::Topology;
 
+/// The bits of a cell that hold its number of adjacent mines.
+/// Five bits are needed, since a cell of a layered board can have 26 neighbours.
+const COUNT_MASK: u16 = 0b0_0001_1111;
+/// Set if the cell contains a mine.
+const MINE: u16 = 0b0_0010_0000;
+/// Set if the cell has been revealed.
+const REVEALED: u16 = 0b0_0100_0000;
+/// Set if the cell has been flagged as a mine.
+const FLAGGED: u16 = 0b0_1000_0000;
+/// Set if the cell has been marked with a question mark.
+const QUESTION: u16 = 0b1_0000_0000;
+
+/// A cell position on the minefield.
+///
+/// Every cell of the board is addressed by this type, so the column and row can
+/// never be swapped by accident.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Pos {
+    /// The column of the cell.
+    pub x: usize,
+    /// The row of the cell.
+    pub y: usize,
+}
+
+impl Pos {
+    /// Creates a new `Pos` from a column and a row.
+    pub fn new(x: usize, y: usize) -> Pos {
+        Pos { x, y }
+    }
+}
+
+/// A move made by the player on the board.
+///
+/// Keyboard and mouse input are both turned into actions, so every way of playing
+/// goes through `Minefield::apply` and can be recorded in the same action log.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// Reveal the cell, flood filling if it has no adjacent mines.
+    Reveal(Pos),
+    /// Cycle the mark on the cell: flag, then question mark, then none.
+    Mark(Pos),
+    /// Chord on the revealed number in the cell.
+    Chord(Pos),
+}
+
+/// An applied action together with the previous contents of every cell it changed,
+/// so that it can be undone.
+#[derive(Clone)]
+struct Change {
+    action: Action,
+    /// Whether an assist made the action rather than the player.
+    assisted: bool,
+    cells: Vec<(usize, u16)>,
+    revealed: usize,
+    flagged: usize,
+}
+
+/// A Minesweeper board together with the player's progress on it.
+///
+/// The board is stored as a flat array with one 16-bit word per cell, in row-major order.
+/// Each word packs the precomputed number of adjacent mines together with the
+/// `MINE`, `REVEALED`, `FLAGGED` and `QUESTION` bits, so drawing a cell never has to
+/// look at its neighbours.
+///
+/// Every action applied through `apply` is journalled, so it can be undone and redone.
+#[derive(Clone)]
 pub struct Minefield {
-    pub grid: HashSet<(usize, usize)>,
     pub width: usize,
     pub height: usize,
+    /// Which cells are neighbours, used for counting, flood filling and drawing.
+    pub topology: Topology,
+    cells: Vec<u16>,
+    mines: usize,
+    revealed: usize,
+    flagged: usize,
+    /// The cells changed by the action being applied, with their previous contents.
+    journal: Vec<(usize, u16)>,
+    /// The applied actions that can be undone, most recent last.
+    history: Vec<Change>,
+    /// The undone actions that can be redone, most recently undone last, each with
+    /// whether an assist made it.
+    undone: Vec<(Action, bool)>,
 }
 
 /// Generates a `Minefield` with the specified dimensions and number of mines.
@@ -21,6 +101,7 @@ pub struct Minefield {
 /// * `width: usize` - The width of the minefield.
 /// * `height: usize` - The height of the minefield.
 /// * `mines: usize` - The number of mines to place in the minefield.
+/// * `topology: Topology` - Which cells are neighbours of each other.
 ///
 /// # Returns
 ///
@@ -29,164 +110,619 @@ pub fn generate_minefield(
     width: usize,
     height: usize,
     mines: usize,
+    topology: Topology,
 ) -> Minefield {
-    // Create a new empty HashSet to store the positions of the mines.
-    let mut mine_positions = HashSet::new();
-
     // Create a new thread-local random number generator.
     let mut rng = rand::thread_rng();
+    place_random_mines(Minefield::new(width, height, topology), mines, &mut rng)
+}
 
-    // Loop until the specified number of mines have been placed.
-    while mine_positions.len() < mines {
-        // Generate a random number between 0 and the width of the minefield.
-        let x = rng.gen_range(0..width);
+/// Generates a `Minefield` like `generate_minefield`, but from a seed, so the same
+/// seed always gives the same board. This lets benchmarks replay identical games.
+///
+/// # Arguments
+///
+/// * `width: usize` - The width of the minefield.
+/// * `height: usize` - The height of the minefield.
+/// * `mines: usize` - The number of mines to place in the minefield.
+/// * `topology: Topology` - Which cells are neighbours of each other.
+/// * `seed: u64` - The seed of the random number generator.
+pub fn generate_seeded_minefield(
+    width: usize,
+    height: usize,
+    mines: usize,
+    topology: Topology,
+    seed: u64,
+) -> Minefield {
+    let mut rng = StdRng::seed_from_u64(seed);
+    place_random_mines(Minefield::new(width, height, topology), mines, &mut rng)
+}
 
-        // Generate a random number between 0 and the height of the minefield.
-        let y = rng.gen_range(0..height);
+/// Places `mines` mines on distinct random cells of an empty minefield.
+fn place_random_mines(mut minefield: Minefield, mines: usize, rng: &mut impl Rng) -> Minefield {
+    let cells = minefield.width * minefield.height;
 
-        // Insert the randomly generated coordinates into the HashSet.
-        // If the coordinates already exist in the HashSet, the insert will fail,
-        // but that's okay because we'll just try again.
-        mine_positions.insert((x, y));
+    // Pick distinct cells for the mines in one pass, which stays fast even when
+    // the board is almost full of mines.
+    for i in index::sample(rng, cells, mines.min(cells)) {
+        minefield.place_mine(minefield.pos(i));
     }
 
-    // Create a new `Minefield` struct containing the grid with the randomly placed
-    // mines.
-    Minefield { grid: mine_positions, width, height }
+    minefield
 }
 
-/// Counts the number of adjacent mines to a given cell in the minefield.
-///
-/// # Arguments
-/// * `minefield: &Minefield` - A reference to the minefield.
-/// * `cell_x: usize` - The x coordinate of the cell.
-/// * `cell_y: usize` - The y coordinate of the cell.
-///
-/// # Returns
-/// The number of mines adjacent to the specified cell.
-///
-/// This function works by iterating over the 8 possible adjacent cells to the
-/// given cell. For each adjacent cell, it checks if the cell is a mine by looking
-/// it up in the `minefield`'s `grid` HashSet. If the cell is a mine, it increments
-/// the count.
-pub fn count_adjacent_mines(minefield: &Minefield, cell_x: usize, cell_y: usize) -> u8 {
-    let mut count = 0;
-
-    // Iterate over the 8 possible adjacent cells
-    for &(dx, dy) in &[
-        // Top-left
-        (-1, -1),
-        // Top
-        (-1, 0),
-        // Top-right
-        (-1, 1),
-        // Left
-        (0, -1),
-        // Right
-        (0, 1),
-        // Bottom-left
-        (1, -1),
-        // Bottom
-        (1, 0),
-        // Bottom-right
-        (1, 1),
-    ] {
-        let nx = cell_x as isize + dx;
-        let ny = cell_y as isize + dy;
-
-        // Check if the adjacent cell is within the bounds of the minefield.
-        if nx >= 0
-            && ny >= 0
-            && nx < minefield.width as isize
-            && ny < minefield.height as isize
-        {
-            // If the adjacent cell is within the bounds, check if it's a mine.
-            if minefield.grid.contains(&(nx as usize, ny as usize)) {
-                // If the adjacent cell is a mine, increment the count.
-                count += 1;
+impl Minefield {
+    /// Creates an empty minefield of the given size and topology, with no mines.
+    pub fn new(width: usize, height: usize, topology: Topology) -> Minefield {
+        Minefield {
+            width,
+            height,
+            topology,
+            cells: vec![0; width * height],
+            mines: 0,
+            revealed: 0,
+            flagged: 0,
+            journal: Vec::new(),
+            history: Vec::new(),
+            undone: Vec::new(),
+        }
+    }
+
+    /// Sets the contents of the cell at `index`, journalling its previous contents.
+    fn set(&mut self, index: usize, cell: u16) {
+        self.journal.push((index, self.cells[index]));
+        self.cells[index] = cell;
+    }
+
+    /// Returns the index of `pos` in the flat cell array.
+    fn index(&self, pos: Pos) -> usize {
+        pos.y * self.width + pos.x
+    }
+
+    /// Returns the position of the cell at `index` in the flat cell array.
+    fn pos(&self, index: usize) -> Pos {
+        Pos::new(index % self.width, index / self.width)
+    }
+
+    /// Returns every cell of the minefield, in row-major order.
+    pub fn positions(&self) -> impl Iterator<Item = Pos> + '_ {
+        (0..self.width * self.height).map(|i| self.pos(i))
+    }
+
+    /// Places a mine at `pos` and updates the counts of its neighbours.
+    pub fn place_mine(&mut self, pos: Pos) {
+        let i = self.index(pos);
+        if self.cells[i] & MINE != 0 {
+            return;
+        }
+        self.cells[i] |= MINE;
+        self.mines += 1;
+        for n in self.neighbours(pos) {
+            let j = self.index(n);
+            self.cells[j] += 1;
+        }
+    }
+
+    /// Sets the player's state of the cell at `pos` directly, without flood filling and
+    /// without adding to the undo history. Used to restore a saved board.
+    pub fn restore_cell(&mut self, pos: Pos, revealed: bool, flagged: bool, questioned: bool) {
+        let i = self.index(pos);
+        let old = self.cells[i];
+        let state = if revealed {
+            REVEALED
+        } else if flagged {
+            FLAGGED
+        } else if questioned {
+            QUESTION
+        } else {
+            0
+        };
+        let new = (old & (MINE | COUNT_MASK)) | state;
+
+        // Keep the revealed and flagged counters in step with the cell.
+        if old & (REVEALED | MINE) == REVEALED {
+            self.revealed -= 1;
+        }
+        if new & (REVEALED | MINE) == REVEALED {
+            self.revealed += 1;
+        }
+        if old & FLAGGED != 0 {
+            self.flagged -= 1;
+        }
+        if new & FLAGGED != 0 {
+            self.flagged += 1;
+        }
+        self.cells[i] = new;
+    }
+
+    /// Returns the number of mines on the board.
+    pub fn mine_count(&self) -> usize {
+        self.mines
+    }
+
+    /// Returns the number of flags placed on the board.
+    pub fn flag_count(&self) -> usize {
+        self.flagged
+    }
+
+    /// Returns `true` if the cell at `pos` contains a mine.
+    pub fn is_mine(&self, pos: Pos) -> bool {
+        self.cells[self.index(pos)] & MINE != 0
+    }
+
+    /// Returns `true` if the cell at `pos` has been revealed.
+    pub fn is_revealed(&self, pos: Pos) -> bool {
+        self.cells[self.index(pos)] & REVEALED != 0
+    }
+
+    /// Returns `true` if the cell at `pos` has been flagged.
+    pub fn is_flagged(&self, pos: Pos) -> bool {
+        self.cells[self.index(pos)] & FLAGGED != 0
+    }
+
+    /// Returns `true` if the cell at `pos` has been marked with a question mark.
+    pub fn is_questioned(&self, pos: Pos) -> bool {
+        self.cells[self.index(pos)] & QUESTION != 0
+    }
+
+    /// Returns `true` once every safe cell has been revealed.
+    pub fn is_won(&self) -> bool {
+        self.revealed + self.mines == self.cells.len()
+    }
+
+    /// Returns the neighbours of `pos`, as defined by the board's topology.
+    ///
+    /// Every rule that looks at neighbours, from counting mines to flood filling and
+    /// chording, goes through here.
+    ///
+    /// # Arguments
+    /// * `pos: Pos` - The cell whose neighbours are wanted.
+    pub fn neighbours(&self, pos: Pos) -> impl Iterator<Item = Pos> {
+        self.topology.neighbours(self.width, self.height, pos)
+    }
+
+    /// Returns the number of rows in each layer, which is the whole height on a flat board.
+    pub fn layer_height(&self) -> usize {
+        self.topology.layer_height(self.height)
+    }
+
+    /// Returns the number of mines adjacent to the cell at `pos`.
+    ///
+    /// The count is precomputed when the mines are placed, so this is a lookup.
+    pub fn adjacent_mines(&self, pos: Pos) -> u8 {
+        (self.cells[self.index(pos)] & COUNT_MASK) as u8
+    }
+
+    /// Counts the number of flagged cells adjacent to a given cell in the minefield.
+    /// Mines already set off, which only stay on the board in lives mode, count as
+    /// flags too, since they are just as known.
+    pub fn adjacent_flags(&self, pos: Pos) -> u8 {
+        self.neighbours(pos)
+            .filter(|&n| self.is_flagged(n) || (self.is_revealed(n) && self.is_mine(n)))
+            .count() as u8
+    }
+
+    /// Returns every mine that has been set off.
+    pub fn exploded_mines(&self) -> impl Iterator<Item = Pos> + '_ {
+        self.positions().filter(|&p| self.is_revealed(p) && self.is_mine(p))
+    }
+
+    /// Returns the number of lives left of a game started with `lives` lives, one less
+    /// for every mine set off. A game is only lost once this reaches zero.
+    pub fn lives_left(&self, lives: u32) -> u32 {
+        lives.saturating_sub(self.exploded_mines().count() as u32)
+    }
+
+    /// Counts the flags and mines shown on the post-mortem of a game.
+    ///
+    /// # Returns
+    /// The number of correctly flagged mines, of flags on safe cells, and of mines that
+    /// were neither flagged nor set off.
+    pub fn flag_tally(&self) -> (usize, usize, usize) {
+        let mines: Vec<Pos> = self.positions().filter(|&p| self.is_mine(p)).collect();
+        let correct = mines.iter().filter(|&&p| self.is_flagged(p)).count();
+        let missed = mines.iter().filter(|&&p| !self.is_flagged(p) && !self.is_revealed(p)).count();
+        (correct, self.flag_count() - correct, missed)
+    }
+
+    /// Cycles the mark on the cell at `pos` from none to a flag, from a flag to a
+    /// question mark, and from a question mark back to none. Revealed cells cannot be
+    /// marked.
+    ///
+    /// Question marks are only a note for the player: they do not count as flags and
+    /// do not stop the cell from being revealed.
+    pub fn cycle_mark(&mut self, pos: Pos) {
+        let i = self.index(pos);
+        let cell = self.cells[i];
+        if cell & REVEALED != 0 {
+            return;
+        }
+        if cell & FLAGGED != 0 {
+            self.set(i, (cell & !FLAGGED) | QUESTION);
+            self.flagged -= 1;
+        } else if cell & QUESTION != 0 {
+            self.set(i, cell & !QUESTION);
+        } else {
+            self.set(i, cell | FLAGGED);
+            self.flagged += 1;
+        }
+    }
+
+    /// Reveals the cell at `pos` and, if it has no adjacent mines, flood fills outwards
+    /// through every connected cell that also has no adjacent mines.
+    ///
+    /// The flood fill uses an explicit stack, so even a huge empty board cannot
+    /// overflow the call stack. Flagged cells are never revealed.
+    ///
+    /// # Arguments
+    /// * `pos: Pos` - The cell to reveal.
+    ///
+    /// # Returns
+    /// `true` if the revealed cell is a mine, `false` otherwise.
+    pub fn reveal(&mut self, pos: Pos) -> bool {
+        let start = self.index(pos);
+        if self.cells[start] & (REVEALED | FLAGGED) != 0 {
+            return false;
+        }
+
+        let mut stack = vec![start];
+        while let Some(i) = stack.pop() {
+            if self.cells[i] & (REVEALED | FLAGGED) != 0 {
+                continue;
+            }
+            self.set(i, (self.cells[i] | REVEALED) & !QUESTION);
+            if self.cells[i] & MINE != 0 {
+                continue;
+            }
+            self.revealed += 1;
+
+            // Only cells with no adjacent mines spread the flood fill.
+            if self.cells[i] & COUNT_MASK == 0 {
+                for n in self.neighbours(self.pos(i)) {
+                    let j = self.index(n);
+                    if self.cells[j] & (REVEALED | FLAGGED) == 0 {
+                        stack.push(j);
+                    }
+                }
             }
         }
+
+        self.cells[start] & MINE != 0
     }
 
-    // Return the count of adjacent mines.
-    count
-}
+    /// Chords on a revealed number: if the number of flags around the cell equals its
+    /// number of adjacent mines, every unflagged neighbour is revealed.
+    ///
+    /// This follows the standard rules, so a wrongly placed flag means a mine is revealed
+    /// and the game is lost.
+    ///
+    /// # Arguments
+    /// * `pos: Pos` - The revealed number to chord on.
+    ///
+    /// # Returns
+    /// `true` if chording revealed a mine, `false` otherwise.
+    pub fn chord(&mut self, pos: Pos) -> bool {
+        // Only a revealed, satisfied number can be chorded.
+        if !self.is_revealed(pos) || self.is_mine(pos) {
+            return false;
+        }
+        let adjacent_mines = self.adjacent_mines(pos);
+        if adjacent_mines == 0 || self.adjacent_flags(pos) != adjacent_mines {
+            return false;
+        }
 
-/// Reveal all adjacent cells that are not mines if the cell at position (x, y) has no
-/// adjacent mines or if all adjacent mines have been flagged.
-///
-/// # Arguments
-/// * `minefield: &Minefield` - A reference to the minefield.
-/// * `revealed: &mut HashSet<(usize, usize)>` - The set of revealed cells.
-/// * `flagged: &HashSet<(usize, usize)>` - The set of flagged cells.
-/// * `x: usize` - The x coordinate of the cell.
-/// * `y: usize` - The y coordinate of the cell.
-pub fn reveal_adjacent_zeros(
-    minefield: &Minefield,
-    revealed: &mut HashSet<(usize, usize)>,
-    flagged: &HashSet<(usize, usize)>,
-    x: usize,
-    y: usize,
-) {
-    // If the cell at (y, x) is already revealed, return immediately.
-    if revealed.contains(&(y, x)) {
-        return;
-    }
-
-    // Add the current cell (y, x) to the set of revealed cells.
-    revealed.insert((y, x));
-
-    // Count the number of mines adjacent to the current cell.
-    let adjacent_mines = count_adjacent_mines(minefield, x, y);
-
-    // If there are no adjacent mines, reveal all adjacent cells.
-    if adjacent_mines == 0 {
-        // Iterate over all possible directions (dx, dy) to adjacent cells.
-        for &(dx, dy) in &[
-            (-1, -1), // Top-left
-            (-1, 0),  // Top
-            (-1, 1),  // Top-right
-            (0, -1),  // Left
-            (0, 1),   // Right
-            (1, -1),  // Bottom-left
-            (1, 0),   // Bottom
-            (1, 1),   // Bottom-right
-        ] {
-            // Calculate the coordinates (nx, ny) of the adjacent cell.
-            let nx = (x as isize + dx) as usize;
-            let ny = (y as isize + dy) as usize;
-
-            // Check if the adjacent cell (nx, ny) is within the minefield bounds.
-            if nx < minefield.width && ny < minefield.height {
-                // Recursively reveal the adjacent cell.
-                reveal_adjacent_zeros(minefield, revealed, flagged, nx, ny);
+        let mut detonated = false;
+        let neighbours: Vec<Pos> = self.neighbours(pos).collect();
+        for neighbour in neighbours {
+            detonated |= self.reveal(neighbour);
+        }
+        detonated
+    }
+
+    /// Applies a player action to the board, journalling it so it can be undone.
+    ///
+    /// Applying a new action that changes the board discards any undone actions.
+    ///
+    /// # Returns
+    /// `true` if the action revealed a mine, `false` otherwise.
+    pub fn apply(&mut self, action: Action) -> bool {
+        let (detonated, changed) = self.apply_journalled(action, false);
+        if changed {
+            self.undone.clear();
+        }
+        detonated
+    }
+
+    /// Applies an action made by an assist, like `apply`, but tags it in the history
+    /// so the analysis of the game can tell it apart from the player's own moves.
+    ///
+    /// # Returns
+    /// `true` if the action revealed a mine, `false` otherwise.
+    pub fn apply_assist(&mut self, action: Action) -> bool {
+        let (detonated, changed) = self.apply_journalled(action, true);
+        if changed {
+            self.undone.clear();
+        }
+        detonated
+    }
+
+    /// Applies an action and, if it changed the board, pushes it onto the history.
+    ///
+    /// # Arguments
+    /// * `action: Action` - The action to apply.
+    /// * `assisted: bool` - Whether an assist made the action rather than the player.
+    ///
+    /// # Returns
+    /// Whether the action revealed a mine, and whether it changed the board at all.
+    fn apply_journalled(&mut self, action: Action, assisted: bool) -> (bool, bool) {
+        let (revealed, flagged) = (self.revealed, self.flagged);
+        let detonated = match action {
+            Action::Reveal(pos) => self.reveal(pos),
+            Action::Mark(pos) => {
+                self.cycle_mark(pos);
+                false
             }
+            Action::Chord(pos) => self.chord(pos),
+        };
+        let cells = std::mem::take(&mut self.journal);
+        let changed = !cells.is_empty();
+        if changed {
+            self.history.push(Change { action, assisted, cells, revealed, flagged });
         }
-    } else {
-        // Count the number of flagged cells adjacent to the current cell.
-        let flagged_count = (0..8)
-            .filter(|&i| flagged.contains(&((y as isize + DY[i]) as usize, (x as isize + DX[i]) as usize)))
-            .count() as u8;
-
-        // If the number of flagged cells equals the number of adjacent mines,
-        // reveal all non-mine adjacent cells.
-        if flagged_count == adjacent_mines {
-            // Iterate over all possible directions (dx, dy) to adjacent cells.
-            for i in 0..8 {
-                // Calculate the coordinates (nx, ny) of the adjacent cell.
-                let nx = (x as isize + DX[i]) as usize;
-                let ny = (y as isize + DY[i]) as usize;
-
-                // Check if the adjacent cell (nx, ny) is within the minefield bounds
-                // and is not a mine.
-                if nx < minefield.width && ny < minefield.height && !minefield.grid.contains(&(ny, nx)) {
-                    // Recursively reveal the adjacent cell.
-                    reveal_adjacent_zeros(minefield, revealed, flagged, nx, ny);
+        (detonated, changed)
+    }
+
+    /// Undoes the most recent action that changed the board.
+    ///
+    /// # Returns
+    /// The action that was undone, or `None` if there is nothing to undo.
+    pub fn undo(&mut self) -> Option<Action> {
+        let change = self.history.pop()?;
+        for &(i, cell) in change.cells.iter().rev() {
+            self.cells[i] = cell;
+        }
+        self.revealed = change.revealed;
+        self.flagged = change.flagged;
+        self.undone.push((change.action, change.assisted));
+        Some(change.action)
+    }
+
+    /// Redoes the most recently undone action.
+    ///
+    /// # Returns
+    /// Whether the redone action revealed a mine, or `None` if there is nothing to redo.
+    pub fn redo(&mut self) -> Option<bool> {
+        let (action, assisted) = self.undone.pop()?;
+        let (detonated, _) = self.apply_journalled(action, assisted);
+        Some(detonated)
+    }
+
+    /// Rewinds a copy of the minefield to before the first action in its undo history,
+    /// so the game can be replayed step by step.
+    ///
+    /// # Returns
+    /// The rewound copy, and the actions that lead from it back to this minefield,
+    /// in the order they were applied, each with whether an assist made it.
+    pub fn rewind(&self) -> (Minefield, Vec<(Action, bool)>) {
+        let mut start = self.clone();
+        start.undone.clear();
+        while start.undo().is_some() {}
+        let actions = start.undone.drain(..).rev().collect();
+        (start, actions)
+    }
+
+    /// Computes the 3BV of the minefield: the minimum number of left clicks needed to
+    /// clear the board without flagging.
+    ///
+    /// Every opening (a connected region of cells with no adjacent mines, together with
+    /// its numbered border) counts as one click, and every numbered cell that does not
+    /// border an opening counts as one click of its own.
+    pub fn three_bv(&self) -> u32 {
+        let mut marked = vec![false; self.cells.len()];
+        let mut stack = Vec::new();
+        let mut clicks = 0;
+
+        // Each opening is cleared by a single click on any of its zero cells.
+        for start in 0..self.cells.len() {
+            if marked[start] || self.cells[start] & (MINE | COUNT_MASK) != 0 {
+                continue;
+            }
+            clicks += 1;
+            stack.push(start);
+            while let Some(i) = stack.pop() {
+                if marked[i] {
+                    continue;
+                }
+                marked[i] = true;
+                if self.cells[i] & COUNT_MASK == 0 {
+                    stack.extend(self.neighbours(self.pos(i)).map(|n| self.index(n)));
                 }
             }
         }
+
+        // Every remaining safe cell needs a click of its own.
+        let isolated = (0..self.cells.len())
+            .filter(|&i| !marked[i] && self.cells[i] & MINE == 0)
+            .count();
+
+        clicks + isolated as u32
     }
 }
 
-static DX: [isize; 8] = [-1, -1, -1, 0, 0, 1, 1, 1];
-static DY: [isize; 8] = [-1, 0, 1, -1, 1, -1, 0, 1];
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a board from rows of `.` for safe cells and `*` for mines.
+    fn board(rows: &[&str]) -> Minefield {
+        let mut minefield = Minefield::new(rows[0].len(), rows.len(), Topology::default());
+        for (y, row) in rows.iter().enumerate() {
+            for (x, ch) in row.chars().enumerate() {
+                if ch == '*' {
+                    minefield.place_mine(Pos::new(x, y));
+                }
+            }
+        }
+        minefield
+    }
+
+    /// Returns whether each cell is revealed, flagged and question-marked, row by row.
+    fn state(minefield: &Minefield) -> Vec<(bool, bool, bool)> {
+        (0..minefield.height)
+            .flat_map(|y| (0..minefield.width).map(move |x| Pos::new(x, y)))
+            .map(|pos| (minefield.is_revealed(pos), minefield.is_flagged(pos), minefield.is_questioned(pos)))
+            .collect()
+    }
+
+    #[test]
+    fn three_bv_counts_openings_and_isolated_numbers() {
+        // The corner mine leaves a single opening with all the numbers on its border.
+        assert_eq!(board(&["*..", "...", "..."]).three_bv(), 1);
+        // The left 1 does not border the opening on the right, so it needs a click of its own.
+        assert_eq!(board(&[".*..."]).three_bv(), 2);
+        // No openings at all: every safe cell is a click.
+        assert_eq!(board(&[".*."]).three_bv(), 2);
+    }
+
+    #[test]
+    fn revealing_a_zero_flood_fills_to_the_numbers() {
+        let mut minefield = board(&["*...", "....", "....", "...."]);
+        assert!(!minefield.reveal(Pos::new(3, 3)));
+        assert!(minefield.is_won());
+    }
+
+    #[test]
+    fn revealing_does_not_spread_past_flags() {
+        let mut minefield = board(&["...."]);
+        minefield.cycle_mark(Pos::new(1, 0));
+        minefield.reveal(Pos::new(3, 0));
+        assert!(!minefield.is_revealed(Pos::new(1, 0)) && !minefield.is_revealed(Pos::new(0, 0)));
+    }
+
+    #[test]
+    fn chording_reveals_the_neighbours_of_a_satisfied_number() {
+        let mut minefield = board(&["*..", "...", "..."]);
+        minefield.reveal(Pos::new(1, 1));
+        assert!(!minefield.chord(Pos::new(1, 1)));
+        assert!(!minefield.is_revealed(Pos::new(2, 2)), "chorded without the flag");
+
+        minefield.cycle_mark(Pos::new(0, 0));
+        assert!(!minefield.chord(Pos::new(1, 1)));
+        assert!(minefield.is_won());
+    }
+
+    #[test]
+    fn chording_around_a_wrong_flag_sets_off_the_mine() {
+        let mut minefield = board(&["*..", "...", "..."]);
+        minefield.reveal(Pos::new(1, 1));
+        minefield.cycle_mark(Pos::new(1, 0));
+        assert!(minefield.chord(Pos::new(1, 1)));
+        assert!(minefield.is_revealed(Pos::new(0, 0)));
+    }
+
+    #[test]
+    fn marks_cycle_through_a_flag_and_a_question_mark() {
+        let mut minefield = board(&["*."]);
+        let pos = Pos::new(0, 0);
+        minefield.cycle_mark(pos);
+        assert!(minefield.is_flagged(pos) && minefield.flag_count() == 1);
+        minefield.cycle_mark(pos);
+        assert!(minefield.is_questioned(pos) && minefield.flag_count() == 0);
+        minefield.cycle_mark(pos);
+        assert!(!minefield.is_flagged(pos) && !minefield.is_questioned(pos));
+    }
+
+    #[test]
+    fn undo_and_redo_restore_the_board() {
+        let mut minefield = board(&["*..", "...", "..."]);
+        let fresh = state(&minefield);
+        assert!(!minefield.apply(Action::Mark(Pos::new(0, 0))));
+        assert!(!minefield.apply(Action::Reveal(Pos::new(2, 2))));
+        let played = state(&minefield);
+        assert_eq!(minefield.flag_count(), 1);
+
+        assert_eq!(minefield.undo(), Some(Action::Reveal(Pos::new(2, 2))));
+        assert_eq!(minefield.undo(), Some(Action::Mark(Pos::new(0, 0))));
+        assert_eq!(minefield.undo(), None);
+        assert_eq!(state(&minefield), fresh);
+        assert_eq!(minefield.flag_count(), 0);
+
+        assert_eq!(minefield.redo(), Some(false));
+        assert_eq!(minefield.redo(), Some(false));
+        assert_eq!(minefield.redo(), None);
+        assert_eq!(state(&minefield), played);
+        assert!(minefield.is_won());
+    }
+
+    #[test]
+    fn a_new_move_discards_the_undone_moves_but_a_no_op_does_not() {
+        let mut minefield = board(&[".*."]);
+        minefield.apply(Action::Reveal(Pos::new(0, 0)));
+        minefield.undo();
+        // Chording on a hidden cell changes nothing, so the reveal can still be redone.
+        minefield.apply(Action::Chord(Pos::new(2, 0)));
+        assert_eq!(minefield.redo(), Some(false));
+
+        minefield.undo();
+        minefield.apply(Action::Mark(Pos::new(1, 0)));
+        assert_eq!(minefield.redo(), None);
+    }
+
+    #[test]
+    fn rewind_replays_to_the_same_board() {
+        let mut minefield = board(&["*..", "...", "..."]);
+        let fresh = state(&minefield);
+        minefield.apply(Action::Reveal(Pos::new(1, 1)));
+        minefield.apply(Action::Mark(Pos::new(0, 0)));
+        minefield.apply(Action::Chord(Pos::new(1, 1)));
+
+        let (mut start, actions) = minefield.rewind();
+        assert_eq!(state(&start), fresh);
+        for (action, _) in actions {
+            start.apply(action);
+        }
+        assert_eq!(state(&start), state(&minefield));
+    }
+
+    #[test]
+    fn a_mine_costs_one_life_and_the_game_goes_on() {
+        let mut minefield = board(&["*..", "...", "..*"]);
+        assert!(minefield.apply(Action::Reveal(Pos::new(0, 0))));
+        assert_eq!(minefield.lives_left(3), 2);
+        assert_eq!(minefield.lives_left(1), 0);
+        assert_eq!(minefield.exploded_mines().collect::<Vec<_>>(), vec![Pos::new(0, 0)]);
+
+        // The set-off mine counts as a flag, so the 1 next to it can be chorded.
+        assert!(!minefield.apply(Action::Reveal(Pos::new(1, 0))));
+        assert!(!minefield.apply(Action::Chord(Pos::new(1, 0))));
+        assert!(minefield.is_revealed(Pos::new(0, 1)));
+        assert!(!minefield.is_won());
+
+        assert!(minefield.apply(Action::Reveal(Pos::new(2, 2))));
+        assert_eq!(minefield.lives_left(3), 1);
+        assert_eq!(minefield.lives_left(2), 0);
+
+        // Set-off mines do not stand in the way of a win.
+        let safe: Vec<Pos> = minefield.positions().filter(|&p| !minefield.is_mine(p)).collect();
+        for pos in safe {
+            minefield.apply(Action::Reveal(pos));
+        }
+        assert!(minefield.is_won());
+    }
+
+    #[test]
+    fn the_flag_tally_counts_correct_and_wrong_flags_and_missed_mines() {
+        let mut minefield = board(&["*.*", "...", "*.*"]);
+        // One mine flagged, one safe cell flagged, one mine set off and two left alone.
+        minefield.apply(Action::Mark(Pos::new(0, 0)));
+        minefield.apply(Action::Mark(Pos::new(1, 1)));
+        minefield.apply(Action::Reveal(Pos::new(2, 0)));
+        // A question mark is not a flag, and the mine under it is still missed.
+        minefield.apply(Action::Mark(Pos::new(0, 2)));
+        minefield.apply(Action::Mark(Pos::new(0, 2)));
+        assert_eq!(minefield.flag_tally(), (1, 1, 2));
+
+        minefield.apply(Action::Mark(Pos::new(2, 2)));
+        assert_eq!(minefield.flag_tally(), (2, 1, 1));
+    }
+}
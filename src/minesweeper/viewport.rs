@@ -0,0 +1,166 @@
+use super::utils::Pos;
+
+/// The number of cells kept between the cursor and the edge of the viewport, when
+/// the board is large enough to scroll.
+const MARGIN: usize = 3;
+
+/// The part of the board that is currently shown on the screen.
+///
+/// The viewport acts as a camera that follows the cursor, so boards larger than the
+/// terminal can still be played. Only the cells inside it are ever drawn.
+pub struct Viewport {
+    /// The column of the board shown at the left edge of the viewport.
+    pub x: usize,
+    /// The row of the board shown at the top edge of the viewport.
+    pub y: usize,
+    /// The number of board columns that fit on the screen.
+    pub columns: usize,
+    /// The number of board rows that fit on the screen.
+    pub rows: usize,
+}
+
+impl Viewport {
+    /// Creates a viewport showing the top-left corner of the board.
+    pub fn new() -> Viewport {
+        Viewport { x: 0, y: 0, columns: 0, rows: 0 }
+    }
+
+    /// Resizes the viewport to the space available on the screen and scrolls it so
+    /// that the cursor stays at least `MARGIN` cells away from its edges.
+    ///
+    /// On a layered board only the layer holding the cursor is shown, and switching
+    /// layers keeps the same part of the layer on screen.
+    ///
+    /// # Arguments
+    /// * `board`: The width and height of the board, or of one layer on a layered board.
+    /// * `space`: The number of columns and rows available on the screen.
+    /// * `cursor`: The position of the cursor.
+    pub fn follow(&mut self, board: (usize, usize), space: (usize, usize), cursor: Pos) {
+        self.columns = board.0.min(space.0);
+        self.rows = board.1.min(space.1);
+        self.x = scroll(self.x, self.columns, board.0, cursor.x);
+        let top = cursor.y - cursor.y % board.1;
+        self.y = top + scroll(self.y % board.1, self.rows, board.1, cursor.y - top);
+    }
+
+    /// Maps a screen position, relative to the top-left corner of the drawn board, to
+    /// the board cell shown there.
+    ///
+    /// # Returns
+    /// The cell under that position, or `None` if it lies outside the viewport.
+    pub fn cell_at(&self, column: i32, row: i32) -> Option<Pos> {
+        let (column, row) = (usize::try_from(column).ok()?, usize::try_from(row).ok()?);
+        if column < self.columns && row < self.rows {
+            Some(Pos::new(self.x + column, self.y + row))
+        } else {
+            None
+        }
+    }
+}
+
+/// Scrolls one axis of the viewport so `cursor` keeps its margin, without ever
+/// scrolling past the end of the board.
+///
+/// # Arguments
+/// * `offset`: The current first visible cell along the axis.
+/// * `visible`: The number of cells visible along the axis.
+/// * `length`: The length of the board along the axis.
+/// * `cursor`: The cursor coordinate along the axis.
+///
+/// # Returns
+/// The new first visible cell along the axis.
+fn scroll(offset: usize, visible: usize, length: usize, cursor: usize) -> usize {
+    if visible == 0 {
+        return 0;
+    }
+    // Small viewports cannot afford the full margin on both sides.
+    let margin = MARGIN.min((visible - 1) / 2);
+    let mut offset = offset;
+    if cursor < offset + margin {
+        offset = cursor.saturating_sub(margin);
+    } else if cursor + margin >= offset + visible {
+        offset = cursor + margin + 1 - visible;
+    }
+    offset.min(length - visible)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a viewport of 10 by 5 cells that has followed the cursor to `cursor` on a
+    /// board of 30 by 16.
+    fn followed(cursor: (usize, usize)) -> Viewport {
+        let mut viewport = Viewport::new();
+        viewport.follow((30, 16), (10, 5), Pos::new(cursor.0, cursor.1));
+        viewport
+    }
+
+    #[test]
+    fn the_viewport_does_not_scroll_while_the_cursor_keeps_its_margin() {
+        let mut viewport = followed((0, 0));
+        for x in 0..=6 {
+            viewport.follow((30, 16), (10, 5), Pos::new(x, 0));
+            assert_eq!(viewport.x, 0, "cursor at column {}", x);
+        }
+        // One more step and the cursor would be closer than 3 cells to the right edge.
+        viewport.follow((30, 16), (10, 5), Pos::new(7, 0));
+        assert_eq!(viewport.x, 1);
+    }
+
+    #[test]
+    fn scrolling_back_keeps_the_margin_on_the_other_side() {
+        let mut viewport = followed((20, 0));
+        assert_eq!(viewport.x, 14);
+        viewport.follow((30, 16), (10, 5), Pos::new(17, 0));
+        assert_eq!(viewport.x, 14);
+        viewport.follow((30, 16), (10, 5), Pos::new(16, 0));
+        assert_eq!(viewport.x, 13);
+    }
+
+    #[test]
+    fn the_viewport_stops_at_the_edges_of_the_board() {
+        let viewport = followed((29, 15));
+        assert_eq!((viewport.x, viewport.y), (20, 11));
+        let viewport = followed((0, 0));
+        assert_eq!((viewport.x, viewport.y), (0, 0));
+    }
+
+    #[test]
+    fn a_small_viewport_gives_up_part_of_the_margin() {
+        // Five rows only leave room for a margin of two above and below the cursor.
+        let mut viewport = followed((0, 5));
+        assert_eq!(viewport.y, 3);
+        viewport.follow((30, 16), (10, 5), Pos::new(0, 4));
+        assert_eq!(viewport.y, 2);
+    }
+
+    #[test]
+    fn a_board_smaller_than_the_screen_never_scrolls() {
+        let mut viewport = Viewport::new();
+        viewport.follow((9, 9), (80, 24), Pos::new(8, 8));
+        assert_eq!((viewport.x, viewport.y, viewport.columns, viewport.rows), (0, 0, 9, 9));
+    }
+
+    #[test]
+    fn switching_layers_keeps_the_same_part_of_the_layer() {
+        // Three layers of 16 rows, stored one below the other.
+        let mut viewport = Viewport::new();
+        viewport.follow((30, 16), (10, 5), Pos::new(0, 10));
+        assert_eq!(viewport.y, 8);
+        viewport.follow((30, 16), (10, 5), Pos::new(0, 26));
+        assert_eq!(viewport.y, 24);
+    }
+
+    #[test]
+    fn screen_cells_map_through_the_scroll_offset() {
+        let viewport = followed((20, 10));
+        assert_eq!((viewport.x, viewport.y), (14, 8));
+        assert_eq!(viewport.cell_at(0, 0), Some(Pos::new(14, 8)));
+        assert_eq!(viewport.cell_at(9, 4), Some(Pos::new(23, 12)));
+        // Anything past the edges of the viewport is off the board.
+        assert_eq!(viewport.cell_at(10, 0), None);
+        assert_eq!(viewport.cell_at(0, 5), None);
+        assert_eq!(viewport.cell_at(-1, 0), None);
+    }
+}
@@ -0,0 +1,112 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// A record of a finished game that is stored as one tab separated line of a
+/// records file.
+///
+/// Each game keeps its own records file in `~/.games-in-rust` and its own fields,
+/// but they are all appended and read back the same way.
+pub trait TsvRecord: Sized {
+    /// Serialises the record as one tab separated line.
+    fn to_line(&self) -> String;
+
+    /// Parses a record from a line written by `to_line`.
+    ///
+    /// # Returns
+    ///
+    /// The record, or `None` if the line is not a record, so corrupt lines are skipped.
+    fn from_line(line: &str) -> Option<Self>;
+}
+
+/// Returns the path of the records file with the given name, in `~/.games-in-rust`.
+fn records_path(file_name: &str) -> Option<PathBuf> {
+    let home = std::env::var_os("HOME")?;
+    Some(PathBuf::from(home).join(".games-in-rust").join(file_name))
+}
+
+/// Appends a record to the records file with the given name, creating the file if
+/// needed.
+pub fn save(file_name: &str, record: &impl TsvRecord) -> io::Result<()> {
+    let path = records_path(file_name).ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "HOME is not set"))?;
+    append(&path, record)
+}
+
+/// Loads every record stored in the records file with the given name.
+///
+/// A missing file simply means no games have been recorded yet.
+pub fn load<R: TsvRecord>(file_name: &str) -> Vec<R> {
+    records_path(file_name).map(|path| read(&path)).unwrap_or_default()
+}
+
+/// Appends a record to a records file, creating the file and its directory if needed.
+fn append(path: &Path, record: &impl TsvRecord) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", record.to_line())
+}
+
+/// Reads every record in a records file, skipping the lines that cannot be parsed.
+/// A file that cannot be read holds no records.
+fn read<R: TsvRecord>(path: &Path) -> Vec<R> {
+    fs::read_to_string(path)
+        .map(|contents| contents.lines().filter_map(R::from_line).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A record of a name and a score, standing in for the records of the games.
+    #[derive(Debug, PartialEq)]
+    struct Score {
+        name: String,
+        points: u32,
+    }
+
+    impl TsvRecord for Score {
+        fn to_line(&self) -> String {
+            format!("{}\t{}", self.name, self.points)
+        }
+
+        fn from_line(line: &str) -> Option<Score> {
+            let (name, points) = line.split_once('\t')?;
+            Some(Score { name: name.to_string(), points: points.parse().ok()? })
+        }
+    }
+
+    /// Returns a records file in a directory of its own, which does not exist yet.
+    fn scratch(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("games-in-rust-records-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join("records.tsv")
+    }
+
+    #[test]
+    fn records_are_appended_and_read_back_in_order() {
+        let path = scratch("round-trip");
+        assert_eq!(read::<Score>(&path), []);
+        let scores = [Score { name: "first".to_string(), points: 3 }, Score { name: "second".to_string(), points: 1 }];
+        for score in &scores {
+            append(&path, score).unwrap();
+        }
+        assert_eq!(read::<Score>(&path), scores);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn corrupt_lines_are_skipped() {
+        let path = scratch("corrupt");
+        append(&path, &Score { name: "kept".to_string(), points: 1 }).unwrap();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        writeln!(file, "no tab here\nbroken\tscore\n").unwrap();
+        append(&path, &Score { name: "also kept".to_string(), points: 2 }).unwrap();
+
+        let names: Vec<String> = read::<Score>(&path).into_iter().map(|score| score.name).collect();
+        assert_eq!(names, ["kept", "also kept"]);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
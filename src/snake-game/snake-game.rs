@@ -17,7 +17,7 @@ const HEIGHT: i32 = 15;
 /// # Returns
 /// 
 /// This function does not return any value.
-pub fn snake_game() -> () {
+pub fn snake_game() {
     // Initialize ncurses
     initscr();
 
@@ -74,17 +74,17 @@ impl SnakeGame {
     /// # Returns
     ///
     /// `()` (an empty tuple).
-    pub fn draw(&self) -> () {
+    pub fn draw(&self) {
         // Clear the screen to start drawing the updated game state.
         clear();
 
         // Draw the game boundaries.
         // The top and bottom boundaries are drawn as a horizontal line of '#' characters.
         // The left and right boundaries are drawn as a vertical line of '#' characters.
-        mvhline(0, 0, '#' as u32, WIDTH as i32); // Top boundary
-        mvhline(HEIGHT - 1, 0, '#' as u32, WIDTH as i32); // Bottom boundary
-        mvvline(0, 0, '#' as u32, HEIGHT as i32); // Left boundary
-        mvvline(0, WIDTH - 1, '#' as u32, HEIGHT as i32); // Right boundary
+        mvhline(0, 0, '#' as u32, WIDTH); // Top boundary
+        mvhline(HEIGHT - 1, 0, '#' as u32, WIDTH); // Bottom boundary
+        mvvline(0, 0, '#' as u32, HEIGHT); // Left boundary
+        mvvline(0, WIDTH - 1, '#' as u32, HEIGHT); // Right boundary
 
         // Draw the snake on the screen.
         // For each segment of the snake, draw a '#' character at the segment's position.
@@ -233,7 +233,7 @@ impl SnakeGame {
     ///
     /// If the new direction is the opposite direction, the function does not update
     /// the direction and simply returns without doing anything.
-    pub fn change_direction(&mut self, new_direction: Direction) -> () {
+    pub fn change_direction(&mut self, new_direction: Direction) {
         // Find the opposite direction of the current direction
         let opposite_direction = match self.direction {
             Direction::Up => Direction::Down,